@@ -44,6 +44,8 @@ fn main() {
         allow_mouse_buttons: true,
         allow_mouse_wheel: true,
         allow_mouse_wheel_sideways: true,
+        allow_mouse_motion: false,
+        mouse_motion_threshold: 10.0,
         allow_gamepads: true,
         bindings: {
             bevy_egui_kbgp::KbgpNavBindings::default()
@@ -67,6 +69,10 @@ fn main() {
                     KbgpNavCommand::user(MyActions::Delete),
                 )
         },
+        disabled_widgets: Default::default(),
+        auto_navigation: false,
+        text_edit_escape: Default::default(),
+        debug_overlay: false,
     });
     app.init_state::<MenuState>();
     app.add_systems(Startup, |mut commands: Commands| {
@@ -0,0 +1,109 @@
+use crate::egui;
+use crate::navigation::{is_navigable_widget, scroll_area_part_ids};
+use crate::{kbgp_get, KbgpEguiResponseExt, KbgpEguiUiCtxExt, NodeData};
+
+pub(crate) fn show_combo_box<R, T: 'static + Clone + PartialEq>(
+    ui: &mut egui::Ui,
+    combo_box: egui::ComboBox,
+    cancel_action: T,
+    add_contents: impl FnOnce(&mut egui::Ui) -> R,
+) -> egui::InnerResponse<Option<R>> {
+    let mut popup_layer = None;
+    let mut items = Vec::new();
+    let mut inner_response = combo_box.show_ui(ui, |ui| {
+        popup_layer = Some(ui.layer_id());
+        let focused_item_clicked =
+            ui.input(|input| input.key_pressed(egui::Key::Enter)) && focus_is_in_layer(ui);
+        let widgets_before = layer_widgets(ui.ctx(), ui.layer_id()).len();
+        let result = add_contents(ui);
+        // Only take the widgets added by `add_contents` - the popup itself registers widgets too.
+        items = layer_widgets(ui.ctx(), ui.layer_id());
+        let scroll_area_parts = scroll_area_part_ids(ui.style(), &items);
+        items.drain(..widgets_before);
+        items.retain(|widget| {
+            is_navigable_widget(widget) && !scroll_area_parts.contains(&widget.id)
+        });
+        if ui.kbgp_user_action() == Some(cancel_action) {
+            ui.kbgp_clear_input();
+            ui.close();
+        } else if focused_item_clicked {
+            // egui only closes combo boxes on pointer clicks.
+            ui.close();
+        }
+        result
+    });
+
+    inner_response.response = inner_response.response.kbgp_navigation();
+    let button_id = inner_response.response.id;
+    let ctx = ui.ctx().clone();
+    let open_layer_key = button_id.with("kbgp_combo_box_open_layer");
+    let prev_popup_layer = ctx.data(|data| data.get_temp::<egui::LayerId>(open_layer_key));
+    let is_open = egui::ComboBox::is_open(&ctx, button_id);
+
+    let kbgp = kbgp_get(&ctx);
+    let mut kbgp = kbgp.lock();
+    let common = &mut kbgp.common;
+    let focused = ctx.memory(|memory| memory.focused());
+
+    if let Some(popup_layer) = popup_layer.filter(|_| is_open) {
+        // Register the popup's items so that they can be navigated without `kbgp_navigation`.
+        for item in items.iter() {
+            if common
+                .nodes
+                .get(&item.id)
+                .is_some_and(|node_data| node_data.seen_this_frame)
+            {
+                continue;
+            }
+            common
+                .nodes
+                .insert(item.id, NodeData::new(item.rect, popup_layer, item.enabled));
+        }
+        common.modal_layer = Some(popup_layer);
+        common.modal_layer_seen_this_frame = true;
+
+        if focused == Some(button_id) {
+            if let Some(first_item) = items.iter().find(|item| item.enabled) {
+                ctx.memory_mut(|memory| memory.request_focus(first_item.id));
+            }
+        }
+        ctx.data_mut(|data| data.insert_temp(open_layer_key, popup_layer));
+    } else if let Some(prev_popup_layer) = prev_popup_layer {
+        let focus_was_in_popup = match focused {
+            None => true,
+            Some(focused) => common
+                .nodes
+                .get(&focused)
+                .is_some_and(|node_data| node_data.layer_id == prev_popup_layer),
+        };
+        if focus_was_in_popup {
+            ctx.memory_mut(|memory| memory.request_focus(button_id));
+        }
+        ctx.data_mut(|data| data.remove::<egui::LayerId>(open_layer_key));
+    }
+
+    inner_response
+}
+
+fn focus_is_in_layer(ui: &egui::Ui) -> bool {
+    let Some(focused) = ui.ctx().memory(|memory| memory.focused()) else {
+        return false;
+    };
+    let kbgp = kbgp_get(ui.ctx());
+    let kbgp = kbgp.lock();
+    kbgp.common
+        .nodes
+        .get(&focused)
+        .is_some_and(|node_data| node_data.layer_id == ui.layer_id())
+}
+
+fn layer_widgets(ctx: &egui::Context, layer_id: egui::LayerId) -> Vec<egui::WidgetRect> {
+    ctx.viewport(|viewport| {
+        viewport
+            .this_pass
+            .widgets
+            .get_layer(layer_id)
+            .copied()
+            .collect()
+    })
+}
@@ -0,0 +1,88 @@
+use crate::egui;
+use crate::kbgp_get;
+use crate::navigation::{navigation_target, KbgpNavDirection};
+
+/// Paint the navigation graph on top of the UI, for debugging navigation issues.
+///
+/// Paints:
+///
+/// * The rect of every widget marked with
+///   [`kbgp_navigation`](crate::KbgpEguiResponseExt::kbgp_navigation), colored by the egui layer
+///   (window or area) it was drawn in. Disabled widgets that navigation skips are painted gray.
+/// * A thick outline around the focused widget, with the name of its layer.
+/// * Arrows from the focused widget to the widgets the focus would move to in each direction.
+///
+/// The overlay reflects the widgets registered in the previous frame. It is painted by
+/// [`KbgpPlugin`](crate::KbgpPlugin) when
+/// [`KbgpSettings::debug_overlay`](crate::KbgpSettings::debug_overlay) is set, but can also be
+/// called manually after [`kbgp_prepare`](crate::kbgp_prepare).
+pub fn kbgp_paint_debug_overlay(egui_ctx: &egui::Context) {
+    let kbgp = kbgp_get(egui_ctx);
+    let kbgp = kbgp.lock();
+    let painter = egui_ctx.layer_painter(egui::LayerId::debug());
+
+    for node_data in kbgp.common.nodes.values() {
+        let color = if node_data.is_skipped(kbgp.common.disabled_widgets) {
+            egui::Color32::GRAY
+        } else {
+            layer_color(node_data.layer_id)
+        };
+        painter.rect_stroke(
+            node_data.rect,
+            0.0,
+            egui::Stroke::new(1.0, color),
+            egui::StrokeKind::Outside,
+        );
+    }
+
+    let Some(focused) = egui_ctx.memory(|memory| memory.focused()) else {
+        return;
+    };
+    let Some(focused_data) = kbgp.common.nodes.get(&focused) else {
+        return;
+    };
+    painter.rect_stroke(
+        focused_data.rect,
+        0.0,
+        egui::Stroke::new(3.0, egui::Color32::YELLOW),
+        egui::StrokeKind::Outside,
+    );
+    painter.text(
+        focused_data.rect.left_top(),
+        egui::Align2::LEFT_BOTTOM,
+        format!(
+            "{:?} {:?}",
+            focused_data.layer_id.order, focused_data.layer_id.id
+        ),
+        egui::FontId::monospace(10.0),
+        egui::Color32::YELLOW,
+    );
+
+    for direction in KbgpNavDirection::ALL {
+        let Some(target) = navigation_target(&kbgp.common, Some(focused), direction) else {
+            continue;
+        };
+        let Some(target_data) = kbgp.common.nodes.get(&target) else {
+            continue;
+        };
+        if target == focused {
+            continue;
+        }
+        let color = match direction {
+            KbgpNavDirection::Up => egui::Color32::LIGHT_GREEN,
+            KbgpNavDirection::Down => egui::Color32::LIGHT_RED,
+            KbgpNavDirection::Left => egui::Color32::LIGHT_BLUE,
+            KbgpNavDirection::Right => egui::Color32::ORANGE,
+        };
+        painter.arrow(
+            focused_data.rect.center(),
+            target_data.rect.center() - focused_data.rect.center(),
+            egui::Stroke::new(2.0, color),
+        );
+    }
+}
+
+fn layer_color(layer_id: egui::LayerId) -> egui::Color32 {
+    let hue = (layer_id.id.value() % 360) as f32 / 360.0;
+    egui::ecolor::Hsva::new(hue, 0.8, 0.9, 1.0).into()
+}
@@ -0,0 +1,375 @@
+use std::sync::LazyLock;
+
+use bevy::input::gamepad::{GamepadConnection, GamepadConnectionEvent};
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+
+use crate::{KbgpAxisInput, KbgpInput};
+
+/// Decides how [`KbgpInput`]s and [`KbgpAxisInput`]s are presented to the player.
+///
+/// Implement this to plug in localization tables, and install it with
+/// [`kbgp_set_input_formatter`](crate::KbgpEguiUiCtxExt::kbgp_set_input_formatter). The methods of
+/// [`KbgpDefaultInputFormatter`] are public, so an implementation can delegate to it for the inputs
+/// it does not care to translate.
+pub trait KbgpInputFormatter: 'static + Send + Sync {
+    /// Create a string that describes a single input.
+    fn format_input(&self, input: &KbgpInput) -> String;
+
+    /// Create a string that describes a whole analog axis.
+    fn format_axis(&self, axis: &KbgpAxisInput) -> String;
+
+    /// The text placed between the inputs of a chord.
+    ///
+    /// Also shown between the glyphs of a chord (without the surrounding whitespace). The default
+    /// implementation returns `" & "`.
+    fn chord_separator(&self) -> &str {
+        " & "
+    }
+
+    /// Create a string that describes a chord of multiple inputs.
+    fn format_chord(&self, chord: &mut dyn Iterator<Item = KbgpInput>) -> String {
+        let mut chord_text = String::new();
+        for input in chord {
+            if !chord_text.is_empty() {
+                chord_text.push_str(self.chord_separator());
+            }
+            chord_text.push_str(&self.format_input(&input));
+        }
+        chord_text
+    }
+
+    /// Update the formatter when a gamepad is connected or disconnected.
+    ///
+    /// The default input system calls this on the installed formatter with every
+    /// [`GamepadConnectionEvent`], unless that formatter is shared at the time (e.g. an `Arc` from
+    /// [`kbgp_input_formatter`](crate::KbgpEguiUiCtxExt::kbgp_input_formatter) is kept around).
+    /// Returns `true` if anything was changed. The default implementation does nothing.
+    fn learn_gamepad_connection(&mut self, event: &GamepadConnectionEvent) -> bool {
+        let _ = event;
+        false
+    }
+}
+
+/// The default, English, [`KbgpInputFormatter`].
+///
+/// Keys are named according to the US layout, unless a label was set for them with
+/// [`with_key_label`](Self::with_key_label) or learned with
+/// [`learn_key_label`](Self::learn_key_label):
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy::input::gamepad::GamepadConnectionEvent;
+/// # use bevy::input::keyboard::KeyboardInput;
+/// # use bevy_egui_kbgp::bevy_egui::EguiContexts;
+/// # use bevy_egui_kbgp::prelude::*;
+/// # use bevy_egui_kbgp::KbgpDefaultInputFormatter;
+/// fn learn_keyboard_layout_system(
+///     mut egui_context: EguiContexts,
+///     mut keyboard_events: EventReader<KeyboardInput>,
+///     keys: Res<ButtonInput<KeyCode>>,
+///     mut gamepad_connection_events: EventReader<GamepadConnectionEvent>,
+///     mut formatter: Local<KbgpDefaultInputFormatter>,
+/// ) -> Result {
+///     let mut learned_anything = false;
+///     for event in keyboard_events.read() {
+///         learned_anything |= formatter.learn_key_label(event, &keys);
+///     }
+///     // Keep the gamepad numbers, which are lost when the installed formatter is replaced.
+///     for event in gamepad_connection_events.read() {
+///         learned_anything |= formatter.learn_gamepad_connection(event);
+///     }
+///     if learned_anything {
+///         egui_context.ctx_mut()?.kbgp_set_input_formatter(formatter.clone());
+///     }
+///     Ok(())
+/// }
+/// ```
+///
+/// Gamepads are numbered for the player ("Gamepad 1", "Gamepad 2", ...) with
+/// [`with_gamepad_number`](Self::with_gamepad_number), or in connection order with
+/// [`learn_gamepad_connection`](Self::learn_gamepad_connection). The default input system does the
+/// latter for the installed formatter. Gamepads without a number are just called "Gamepad".
+#[derive(Default, Clone)]
+pub struct KbgpDefaultInputFormatter {
+    /// Labels that override the default names of keyboard keys.
+    pub key_labels: HashMap<KeyCode, String>,
+    /// The player-facing numbers of the gamepads.
+    pub gamepad_numbers: HashMap<Entity, usize>,
+}
+
+const MODIFIER_KEYS: [KeyCode; 8] = [
+    KeyCode::ShiftLeft,
+    KeyCode::ShiftRight,
+    KeyCode::ControlLeft,
+    KeyCode::ControlRight,
+    KeyCode::AltLeft,
+    KeyCode::AltRight,
+    KeyCode::SuperLeft,
+    KeyCode::SuperRight,
+];
+
+static DEFAULT_INPUT_FORMATTER: LazyLock<KbgpDefaultInputFormatter> =
+    LazyLock::new(Default::default);
+
+/// The formatter used when no formatter was set, e.g. in the `Display` implementations.
+pub(crate) fn default_input_formatter() -> &'static KbgpDefaultInputFormatter {
+    &DEFAULT_INPUT_FORMATTER
+}
+
+impl KbgpDefaultInputFormatter {
+    /// Override the name of a keyboard key.
+    pub fn with_key_label(mut self, key: KeyCode, label: impl Into<String>) -> Self {
+        self.key_labels.insert(key, label.into());
+        self
+    }
+
+    /// Use the logical key of a keyboard event as the label of its physical key.
+    ///
+    /// This makes the formatter aware of the player's keyboard layout. Only keys that produce
+    /// characters are learned, and only while no modifier is held in `keys` - the logical key of
+    /// Shift+1 is `!` and of AltGr+Q may be `@`, which are not what is printed on the keys. Returns
+    /// `true` if the label of the key was changed.
+    pub fn learn_key_label(&mut self, event: &KeyboardInput, keys: &ButtonInput<KeyCode>) -> bool {
+        if keys.any_pressed(MODIFIER_KEYS) {
+            return false;
+        }
+        let Key::Character(character) = &event.logical_key else {
+            return false;
+        };
+        if character.chars().all(char::is_whitespace) {
+            return false;
+        }
+        let label = character.to_uppercase();
+        if self.key_labels.get(&event.key_code) == Some(&label) {
+            return false;
+        }
+        self.key_labels.insert(event.key_code, label);
+        true
+    }
+
+    /// Set the player-facing number of a gamepad.
+    pub fn with_gamepad_number(mut self, gamepad: Entity, number: usize) -> Self {
+        self.gamepad_numbers.insert(gamepad, number);
+        self
+    }
+
+    /// Number the gamepads in the order they were connected.
+    ///
+    /// A connected gamepad gets the lowest number not used by another gamepad, starting from 1, and
+    /// a disconnected gamepad frees its number. Returns `true` if the numbers were changed.
+    pub fn learn_gamepad_connection(&mut self, event: &GamepadConnectionEvent) -> bool {
+        match event.connection {
+            GamepadConnection::Connected { .. } => {
+                if self.gamepad_numbers.contains_key(&event.gamepad) {
+                    return false;
+                }
+                let mut number = 1;
+                while self.gamepad_numbers.values().any(|used| *used == number) {
+                    number += 1;
+                }
+                self.gamepad_numbers.insert(event.gamepad, number);
+                true
+            }
+            GamepadConnection::Disconnected => {
+                self.gamepad_numbers.remove(&event.gamepad).is_some()
+            }
+        }
+    }
+
+    /// The name of a keyboard key.
+    pub fn key_name(&self, key: KeyCode) -> String {
+        if let Some(label) = self.key_labels.get(&key) {
+            return label.clone();
+        }
+        let name = match key {
+            KeyCode::Backquote => "`",
+            KeyCode::Backslash => "\\",
+            KeyCode::BracketLeft => "[",
+            KeyCode::BracketRight => "]",
+            KeyCode::Comma => ",",
+            KeyCode::Equal => "=",
+            KeyCode::Minus => "-",
+            KeyCode::Period => ".",
+            KeyCode::Quote => "'",
+            KeyCode::Semicolon => ";",
+            KeyCode::Slash => "/",
+            KeyCode::Escape => "Esc",
+            KeyCode::ArrowUp => "Up",
+            KeyCode::ArrowDown => "Down",
+            KeyCode::ArrowLeft => "Left",
+            KeyCode::ArrowRight => "Right",
+            KeyCode::ControlLeft => "Left Ctrl",
+            KeyCode::ControlRight => "Right Ctrl",
+            KeyCode::AltLeft if cfg!(target_os = "macos") => "Left Option",
+            KeyCode::AltRight if cfg!(target_os = "macos") => "Right Option",
+            KeyCode::SuperLeft if cfg!(target_os = "macos") => "Left Cmd",
+            KeyCode::SuperRight if cfg!(target_os = "macos") => "Right Cmd",
+            KeyCode::SuperLeft if cfg!(target_os = "windows") => "Left Win",
+            KeyCode::SuperRight if cfg!(target_os = "windows") => "Right Win",
+            KeyCode::NumpadAdd => "Numpad +",
+            KeyCode::NumpadSubtract => "Numpad -",
+            KeyCode::NumpadMultiply => "Numpad *",
+            KeyCode::NumpadDivide => "Numpad /",
+            KeyCode::NumpadDecimal => "Numpad .",
+            _ => {
+                let debug_name = format!("{key:?}");
+                if let Some(letter) = debug_name.strip_prefix("Key") {
+                    return letter.to_owned();
+                }
+                if let Some(digit) = debug_name.strip_prefix("Digit") {
+                    return digit.to_owned();
+                }
+                if let KeyCode::Unidentified(_) = key {
+                    return debug_name;
+                }
+                return split_camel_case(&debug_name);
+            }
+        };
+        name.to_owned()
+    }
+
+    /// The name of a mouse button.
+    pub fn mouse_button_name(&self, button: MouseButton) -> String {
+        match button {
+            MouseButton::Left => "Left Click".to_owned(),
+            MouseButton::Right => "Right Click".to_owned(),
+            MouseButton::Middle => "Middle Click".to_owned(),
+            MouseButton::Back => "Mouse Back".to_owned(),
+            MouseButton::Forward => "Mouse Forward".to_owned(),
+            MouseButton::Other(button) => format!("Mouse Button {button}"),
+        }
+    }
+
+    /// The name of a gamepad, used as a prefix for the gamepad's inputs.
+    pub fn gamepad_name(&self, gamepad: Entity) -> String {
+        if let Some(number) = self.gamepad_numbers.get(&gamepad) {
+            format!("Gamepad {number}")
+        } else {
+            "Gamepad".to_owned()
+        }
+    }
+
+    /// The name of a gamepad button, covering the common controller families.
+    pub fn gamepad_button_name(&self, button: GamepadButton) -> String {
+        match button {
+            GamepadButton::South => "A / Cross".to_owned(),
+            GamepadButton::East => "B / Circle".to_owned(),
+            GamepadButton::North => "Y / Triangle".to_owned(),
+            GamepadButton::West => "X / Square".to_owned(),
+            GamepadButton::C => "C".to_owned(),
+            GamepadButton::Z => "Z".to_owned(),
+            GamepadButton::LeftTrigger => "LB / L1".to_owned(),
+            GamepadButton::LeftTrigger2 => "LT / L2".to_owned(),
+            GamepadButton::RightTrigger => "RB / R1".to_owned(),
+            GamepadButton::RightTrigger2 => "RT / R2".to_owned(),
+            GamepadButton::Select => "Select".to_owned(),
+            GamepadButton::Start => "Start".to_owned(),
+            GamepadButton::Mode => "Home".to_owned(),
+            GamepadButton::LeftThumb => "LS / L3".to_owned(),
+            GamepadButton::RightThumb => "RS / R3".to_owned(),
+            GamepadButton::DPadUp => "D-Pad Up".to_owned(),
+            GamepadButton::DPadDown => "D-Pad Down".to_owned(),
+            GamepadButton::DPadLeft => "D-Pad Left".to_owned(),
+            GamepadButton::DPadRight => "D-Pad Right".to_owned(),
+            GamepadButton::Other(button) => format!("Button {button}"),
+        }
+    }
+
+    /// The name of a gamepad axis.
+    ///
+    /// `direction` is `Some(true)` for the positive direction of the axis, `Some(false)` for the
+    /// negative direction, and `None` for the whole axis.
+    pub fn gamepad_axis_name(&self, axis: GamepadAxis, direction: Option<bool>) -> String {
+        let (name, negative, positive) = match axis {
+            GamepadAxis::LeftStickX => ("Left Stick", "Left", "Right"),
+            GamepadAxis::LeftStickY => ("Left Stick", "Down", "Up"),
+            GamepadAxis::RightStickX => ("Right Stick", "Left", "Right"),
+            GamepadAxis::RightStickY => ("Right Stick", "Down", "Up"),
+            GamepadAxis::LeftZ => ("Left Z", "-", "+"),
+            GamepadAxis::RightZ => ("Right Z", "-", "+"),
+            GamepadAxis::Other(axis) => {
+                return match direction {
+                    None => format!("Axis {axis}"),
+                    Some(false) => format!("Axis {axis} -"),
+                    Some(true) => format!("Axis {axis} +"),
+                };
+            }
+        };
+        match direction {
+            None => match axis {
+                GamepadAxis::LeftStickX | GamepadAxis::RightStickX => format!("{name} X"),
+                GamepadAxis::LeftStickY | GamepadAxis::RightStickY => format!("{name} Y"),
+                _ => name.to_owned(),
+            },
+            Some(false) => format!("{name} {negative}"),
+            Some(true) => format!("{name} {positive}"),
+        }
+    }
+}
+
+impl KbgpInputFormatter for KbgpDefaultInputFormatter {
+    fn format_input(&self, input: &KbgpInput) -> String {
+        match input {
+            KbgpInput::Keyboard(key) => self.key_name(*key),
+            KbgpInput::MouseButton(button) => self.mouse_button_name(*button),
+            KbgpInput::MouseWheelUp => "Scroll Up".to_owned(),
+            KbgpInput::MouseWheelDown => "Scroll Down".to_owned(),
+            KbgpInput::MouseWheelLeft => "Scroll Left".to_owned(),
+            KbgpInput::MouseWheelRight => "Scroll Right".to_owned(),
+            KbgpInput::MouseMotionUp => "Mouse Up".to_owned(),
+            KbgpInput::MouseMotionDown => "Mouse Down".to_owned(),
+            KbgpInput::MouseMotionLeft => "Mouse Left".to_owned(),
+            KbgpInput::MouseMotionRight => "Mouse Right".to_owned(),
+            KbgpInput::GamepadAxisPositive(entity, axis) => format!(
+                "{}: {}",
+                self.gamepad_name(*entity),
+                self.gamepad_axis_name(*axis, Some(true))
+            ),
+            KbgpInput::GamepadAxisNegative(entity, axis) => format!(
+                "{}: {}",
+                self.gamepad_name(*entity),
+                self.gamepad_axis_name(*axis, Some(false))
+            ),
+            KbgpInput::GamepadButton(entity, button) => format!(
+                "{}: {}",
+                self.gamepad_name(*entity),
+                self.gamepad_button_name(*button)
+            ),
+        }
+    }
+
+    fn format_axis(&self, axis: &KbgpAxisInput) -> String {
+        match axis {
+            KbgpAxisInput::MouseMotionX => "Mouse X".to_owned(),
+            KbgpAxisInput::MouseMotionY => "Mouse Y".to_owned(),
+            KbgpAxisInput::GamepadAxis(entity, axis) => format!(
+                "{}: {}",
+                self.gamepad_name(*entity),
+                self.gamepad_axis_name(*axis, None)
+            ),
+        }
+    }
+
+    fn learn_gamepad_connection(&mut self, event: &GamepadConnectionEvent) -> bool {
+        KbgpDefaultInputFormatter::learn_gamepad_connection(self, event)
+    }
+}
+
+fn split_camel_case(name: &str) -> String {
+    let mut result = String::with_capacity(name.len() + 4);
+    let mut prev: Option<char> = None;
+    for c in name.chars() {
+        if let Some(prev) = prev {
+            if (c.is_uppercase() && !prev.is_uppercase())
+                || (c.is_ascii_digit() && prev.is_lowercase())
+            {
+                result.push(' ');
+            }
+        }
+        result.push(c);
+        prev = Some(c);
+    }
+    result
+}
@@ -0,0 +1,252 @@
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+
+use crate::egui;
+use crate::{KbgpAxisInput, KbgpCommon, KbgpInput};
+
+/// The family of a gamepad, used for picking the right glyphs for its buttons.
+#[derive(Hash, PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum KbgpControllerFamily {
+    #[default]
+    Generic,
+    Xbox,
+    PlayStation,
+    Nintendo,
+}
+
+impl KbgpControllerFamily {
+    /// Guess the family of a gamepad based on its vendor ID.
+    pub fn detect(gamepad: &Gamepad) -> Self {
+        match gamepad.vendor_id() {
+            Some(0x045e) => Self::Xbox,
+            Some(0x054c) => Self::PlayStation,
+            Some(0x057e) => Self::Nintendo,
+            _ => Self::Generic,
+        }
+    }
+}
+
+/// Images for rendering inputs, taken from a user supplied texture.
+///
+/// Glyphs of gamepad inputs are registered per [`KbgpControllerFamily`], and are looked up by the
+/// family of the gamepad the input came from - falling back to the
+/// [`Generic`](KbgpControllerFamily::Generic) glyph. The gamepad entity inside the input is
+/// ignored when registering glyphs. Keyboard and mouse glyphs are always registered as
+/// [`Generic`](KbgpControllerFamily::Generic).
+///
+/// Install the atlas with
+/// [`kbgp_set_glyph_atlas`](crate::KbgpEguiUiCtxExt::kbgp_set_glyph_atlas) and render glyphs with
+/// [`kbgp_input_glyph`](crate::KbgpEguiUiExt::kbgp_input_glyph):
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_egui_kbgp::{egui, bevy_egui};
+/// # use bevy_egui::EguiContexts;
+/// # use bevy_egui_kbgp::prelude::*;
+/// # use bevy_egui_kbgp::{KbgpControllerFamily, KbgpGlyphAtlas};
+/// fn setup_glyphs_system(
+///     mut egui_context: EguiContexts,
+///     asset_server: Res<AssetServer>,
+/// ) -> Result {
+///     let texture_id = egui_context.add_image(asset_server.load("glyphs.png"));
+///     let atlas = KbgpGlyphAtlas::new(texture_id, egui::vec2(24.0, 24.0))
+///         .with_glyph(
+///             KbgpControllerFamily::Xbox,
+///             KbgpInput::GamepadButton(Entity::PLACEHOLDER, GamepadButton::South),
+///             egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(0.25, 0.25)),
+///         )
+///         .with_glyph(
+///             KbgpControllerFamily::PlayStation,
+///             KbgpInput::GamepadButton(Entity::PLACEHOLDER, GamepadButton::South),
+///             egui::Rect::from_min_max(egui::pos2(0.25, 0.0), egui::pos2(0.5, 0.25)),
+///         );
+///     egui_context.ctx_mut()?.kbgp_set_glyph_atlas(atlas);
+///     Ok(())
+/// }
+/// ```
+pub struct KbgpGlyphAtlas {
+    texture_id: egui::TextureId,
+    /// The size in which glyphs are rendered.
+    pub glyph_size: egui::Vec2,
+    input_glyphs: HashMap<(KbgpControllerFamily, KbgpInput), egui::Rect>,
+    axis_glyphs: HashMap<(KbgpControllerFamily, KbgpAxisInput), egui::Rect>,
+}
+
+impl KbgpGlyphAtlas {
+    /// Create an empty atlas over a texture.
+    pub fn new(texture_id: egui::TextureId, glyph_size: egui::Vec2) -> Self {
+        Self {
+            texture_id,
+            glyph_size,
+            input_glyphs: Default::default(),
+            axis_glyphs: Default::default(),
+        }
+    }
+
+    /// Register the glyph of an input, given as UV coordinates in the texture.
+    pub fn insert_glyph(&mut self, family: KbgpControllerFamily, input: KbgpInput, uv: egui::Rect) {
+        self.input_glyphs
+            .insert(Self::normalize_input(family, &input), uv);
+    }
+
+    /// Register the glyph of an input, given as UV coordinates in the texture.
+    pub fn with_glyph(
+        mut self,
+        family: KbgpControllerFamily,
+        input: KbgpInput,
+        uv: egui::Rect,
+    ) -> Self {
+        self.insert_glyph(family, input, uv);
+        self
+    }
+
+    /// Register the glyph of a whole axis, given as UV coordinates in the texture.
+    pub fn insert_axis_glyph(
+        &mut self,
+        family: KbgpControllerFamily,
+        axis: KbgpAxisInput,
+        uv: egui::Rect,
+    ) {
+        self.axis_glyphs
+            .insert(Self::normalize_axis(family, &axis), uv);
+    }
+
+    /// Register the glyph of a whole axis, given as UV coordinates in the texture.
+    pub fn with_axis_glyph(
+        mut self,
+        family: KbgpControllerFamily,
+        axis: KbgpAxisInput,
+        uv: egui::Rect,
+    ) -> Self {
+        self.insert_axis_glyph(family, axis, uv);
+        self
+    }
+
+    /// The image of an input's glyph, if registered.
+    pub fn glyph(
+        &self,
+        family: KbgpControllerFamily,
+        input: &KbgpInput,
+    ) -> Option<egui::Image<'static>> {
+        let uv = self
+            .input_glyphs
+            .get(&Self::normalize_input(family, input))
+            .or_else(|| {
+                self.input_glyphs
+                    .get(&Self::normalize_input(KbgpControllerFamily::Generic, input))
+            })?;
+        Some(self.image(*uv))
+    }
+
+    /// The image of a whole axis's glyph, if registered.
+    pub fn axis_glyph(
+        &self,
+        family: KbgpControllerFamily,
+        axis: &KbgpAxisInput,
+    ) -> Option<egui::Image<'static>> {
+        let uv = self
+            .axis_glyphs
+            .get(&Self::normalize_axis(family, axis))
+            .or_else(|| {
+                self.axis_glyphs
+                    .get(&Self::normalize_axis(KbgpControllerFamily::Generic, axis))
+            })?;
+        Some(self.image(*uv))
+    }
+
+    fn image(&self, uv: egui::Rect) -> egui::Image<'static> {
+        egui::Image::from_texture(egui::load::SizedTexture::new(
+            self.texture_id,
+            self.glyph_size,
+        ))
+        .uv(uv)
+    }
+
+    fn normalize_input(
+        family: KbgpControllerFamily,
+        input: &KbgpInput,
+    ) -> (KbgpControllerFamily, KbgpInput) {
+        match input {
+            KbgpInput::GamepadAxisPositive(_, axis) => (
+                family,
+                KbgpInput::GamepadAxisPositive(Entity::PLACEHOLDER, *axis),
+            ),
+            KbgpInput::GamepadAxisNegative(_, axis) => (
+                family,
+                KbgpInput::GamepadAxisNegative(Entity::PLACEHOLDER, *axis),
+            ),
+            KbgpInput::GamepadButton(_, button) => (
+                family,
+                KbgpInput::GamepadButton(Entity::PLACEHOLDER, *button),
+            ),
+            _ => (KbgpControllerFamily::Generic, input.clone()),
+        }
+    }
+
+    fn normalize_axis(
+        family: KbgpControllerFamily,
+        axis: &KbgpAxisInput,
+    ) -> (KbgpControllerFamily, KbgpAxisInput) {
+        match axis {
+            KbgpAxisInput::GamepadAxis(_, axis) => (
+                family,
+                KbgpAxisInput::GamepadAxis(Entity::PLACEHOLDER, *axis),
+            ),
+            _ => (KbgpControllerFamily::Generic, axis.clone()),
+        }
+    }
+}
+
+pub(crate) fn show_input_glyph(
+    ui: &mut egui::Ui,
+    common: &KbgpCommon,
+    input: &KbgpInput,
+) -> egui::Response {
+    let text = common.input_formatter.format_input(input);
+    let family = common.controller_family(input.get_source().gamepad());
+    if let Some(image) = common
+        .glyph_atlas
+        .as_ref()
+        .and_then(|atlas| atlas.glyph(family, input))
+    {
+        ui.add(image).on_hover_text(text)
+    } else {
+        ui.label(text)
+    }
+}
+
+pub(crate) fn show_axis_glyph(
+    ui: &mut egui::Ui,
+    common: &KbgpCommon,
+    axis: &KbgpAxisInput,
+) -> egui::Response {
+    let text = common.input_formatter.format_axis(axis);
+    let family = common.controller_family(axis.get_source().gamepad());
+    if let Some(image) = common
+        .glyph_atlas
+        .as_ref()
+        .and_then(|atlas| atlas.axis_glyph(family, axis))
+    {
+        ui.add(image).on_hover_text(text)
+    } else {
+        ui.label(text)
+    }
+}
+
+pub(crate) fn show_chord_glyphs(
+    ui: &mut egui::Ui,
+    common: &KbgpCommon,
+    chord: impl Iterator<Item = KbgpInput>,
+) -> egui::Response {
+    // `horizontal` already spaces the glyphs.
+    let separator = common.input_formatter.chord_separator().trim();
+    ui.horizontal(|ui| {
+        for (i, input) in chord.enumerate() {
+            if 0 < i {
+                ui.label(separator);
+            }
+            show_input_glyph(ui, common, &input);
+        }
+    })
+    .response
+}
@@ -0,0 +1,92 @@
+//! Custom input sources - devices KBGP does not read by itself, that can still drive the
+//! navigation and the key assignment.
+
+use bevy::prelude::*;
+
+use crate::{KbgpPrepareNavigation, KbgpPreparePendingInput};
+
+/// A source of input other than the keyboard, the mouse and the gamepads - e.g. MIDI controllers,
+/// arcade sticks over serial, network remote control or accessibility switches.
+///
+/// Register providers in the [`KbgpInputSourceProviders`] resource. [`KbgpPlugin`](crate::KbgpPlugin)
+/// calls them every frame, after feeding the built-in input:
+///
+/// ```no_run
+/// use std::sync::{Arc, Mutex};
+///
+/// use bevy::prelude::*;
+/// use bevy_egui_kbgp::prelude::*;
+/// use bevy_egui_kbgp::{KbgpInputSourceProvider, KbgpInputSourceProviders, KbgpPrepareNavigation};
+///
+/// /// Commands received over the network, collected by another thread.
+/// struct RemoteControl {
+///     held_commands: Arc<Mutex<Vec<KbgpNavCommand>>>,
+/// }
+///
+/// impl KbgpInputSourceProvider for RemoteControl {
+///     fn navigation(&mut self, prp: &mut KbgpPrepareNavigation) {
+///         for command in self.held_commands.lock().unwrap().iter() {
+///             prp.apply_action(command);
+///         }
+///     }
+/// }
+///
+/// fn setup(mut providers: ResMut<KbgpInputSourceProviders>) {
+///     providers.add(RemoteControl {
+///         held_commands: Default::default(),
+///     });
+/// }
+///
+/// App::new()
+///     .add_plugins(KbgpPlugin)
+///     .add_systems(Startup, setup);
+/// ```
+pub trait KbgpInputSourceProvider: 'static + Send + Sync {
+    /// Feed the input for navigating the UI, e.g. with
+    /// [`apply_action`](KbgpPrepareNavigation::apply_action).
+    ///
+    /// Commands should be applied every frame for as long as they are held - KBGP takes care of
+    /// the repeat timing.
+    fn navigation(&mut self, prp: &mut KbgpPrepareNavigation);
+
+    /// Feed the input for a pending input (e.g. a key assignment button), e.g. with
+    /// [`accept_inputs`](KbgpPreparePendingInput::accept_inputs).
+    ///
+    /// The default implementation feeds nothing, so the source cannot be used for key assignment.
+    fn pending_input(&mut self, prp: &mut KbgpPreparePendingInput) {
+        let _ = prp;
+    }
+}
+
+/// The [`KbgpInputSourceProvider`]s [`KbgpPlugin`](crate::KbgpPlugin) calls every frame.
+#[derive(Resource, Default)]
+pub struct KbgpInputSourceProviders {
+    providers: Vec<Box<dyn KbgpInputSourceProvider>>,
+}
+
+impl KbgpInputSourceProviders {
+    /// Register a provider. Providers are called in the order they were added.
+    pub fn add(&mut self, provider: impl KbgpInputSourceProvider) {
+        self.providers.push(Box::new(provider));
+    }
+
+    /// Feed the navigation input of all the providers.
+    ///
+    /// Only needed in custom systems that call [`kbgp_prepare`](crate::kbgp_prepare) instead of
+    /// using [`KbgpPlugin`](crate::KbgpPlugin).
+    pub fn navigation(&mut self, prp: &mut KbgpPrepareNavigation) {
+        for provider in self.providers.iter_mut() {
+            provider.navigation(prp);
+        }
+    }
+
+    /// Feed the pending input of all the providers.
+    ///
+    /// Only needed in custom systems that call [`kbgp_prepare`](crate::kbgp_prepare) instead of
+    /// using [`KbgpPlugin`](crate::KbgpPlugin).
+    pub fn pending_input(&mut self, prp: &mut KbgpPreparePendingInput) {
+        for provider in self.providers.iter_mut() {
+            provider.pending_input(prp);
+        }
+    }
+}
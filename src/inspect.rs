@@ -0,0 +1,187 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::egui;
+use crate::navigation::{navigation_target, KbgpNavDirection, PendingReleaseState};
+use crate::{kbgp_get, KbgpState};
+
+/// A snapshot of KBGP's navigation graph and state, returned by [`kbgp_inspect`].
+#[derive(Debug, Clone)]
+pub struct KbgpInspection {
+    /// The widgets marked with [`kbgp_navigation`](crate::KbgpEguiResponseExt::kbgp_navigation),
+    /// sorted top to bottom and then left to right.
+    pub nodes: Vec<KbgpNodeInspection>,
+    /// The widget that has the focus.
+    pub focused: Option<egui::Id>,
+    pub state: KbgpStateInspection,
+}
+
+impl KbgpInspection {
+    /// Find a node by its widget ID.
+    pub fn node(&self, id: egui::Id) -> Option<&KbgpNodeInspection> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+}
+
+/// A widget in the navigation graph.
+#[derive(Debug, Clone)]
+pub struct KbgpNodeInspection {
+    pub id: egui::Id,
+    pub rect: egui::Rect,
+    /// The egui layer (window or area) the widget was drawn in.
+    pub layer_id: egui::LayerId,
+    /// The type names of the labels set with
+    /// [`kbgp_focus_label`](crate::KbgpEguiResponseExt::kbgp_focus_label) (or
+    /// [`kbgp_initial_focus`](crate::KbgpEguiResponseExt::kbgp_initial_focus)). Use
+    /// [`has_focus_label`](Self::has_focus_label) to check for a specific label.
+    ///
+    /// Only labels set after `kbgp_navigation` was called on the widget are listed.
+    pub focus_label_types: Vec<&'static str>,
+    focus_labels: Vec<Arc<dyn Any + Send + Sync>>,
+    pub enabled: bool,
+    /// Whether navigation skips this widget because it is disabled. See
+    /// [`KbgpDisabledWidgetPolicy`](crate::KbgpDisabledWidgetPolicy).
+    pub skipped: bool,
+    /// The widget the focus would move to when navigating up from this widget.
+    pub up: Option<egui::Id>,
+    /// The widget the focus would move to when navigating down from this widget.
+    pub down: Option<egui::Id>,
+    /// The widget the focus would move to when navigating left from this widget.
+    pub left: Option<egui::Id>,
+    /// The widget the focus would move to when navigating right from this widget.
+    pub right: Option<egui::Id>,
+}
+
+impl KbgpNodeInspection {
+    /// Check if the widget was marked with a label using
+    /// [`kbgp_focus_label`](crate::KbgpEguiResponseExt::kbgp_focus_label).
+    ///
+    /// Like [`focus_label_types`](Self::focus_label_types), only labels set after
+    /// `kbgp_navigation` was called on the widget are considered.
+    pub fn has_focus_label<T: 'static + PartialEq<T>>(&self, label: &T) -> bool {
+        self.focus_labels
+            .iter()
+            .any(|focus_label| focus_label.downcast_ref::<T>() == Some(label))
+    }
+}
+
+/// The mode KBGP is in.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KbgpStateInspection {
+    /// KBGP is navigating the UI.
+    Navigation {
+        pending_release: KbgpPendingReleaseInspection,
+    },
+    /// KBGP is waiting for the player to enter input.
+    PendingInput,
+}
+
+/// Tracking of the activation button, for the `_released` methods (like
+/// [`kbgp_click_released`](crate::KbgpEguiResponseExt::kbgp_click_released)).
+#[derive(Debug, Clone, PartialEq)]
+pub enum KbgpPendingReleaseInspection {
+    /// Nothing is held.
+    Idle,
+    /// Click or a user action is held while a widget is focused.
+    NodeHeld { id: egui::Id, is_user_action: bool },
+    /// Click or a user action was released on a widget this frame.
+    NodeHoldReleased { id: egui::Id },
+    /// A user action is held while nothing is focused.
+    GloballyHeld,
+    /// A user action was released while nothing was focused.
+    GlobalHoldReleased,
+    /// The input was invalidated (e.g. by
+    /// [`kbgp_clear_input`](crate::KbgpEguiUiCtxExt::kbgp_clear_input)), and the held buttons will
+    /// be ignored until released.
+    Invalidated,
+}
+
+/// Inspect the navigation graph and state of KBGP.
+///
+/// Reflects the widgets registered in the last frame, so it should be called between frames - or
+/// after the UI was drawn. Useful for asserting the navigation layout in tests without simulating
+/// input:
+///
+/// ```no_run
+/// # use bevy_egui_kbgp::egui;
+/// # use bevy_egui_kbgp::kbgp_inspect;
+/// # let ctx: egui::Context = todo!();
+/// # let (play_button_id, options_button_id): (egui::Id, egui::Id) = todo!();
+/// let inspection = kbgp_inspect(&ctx);
+/// assert_eq!(
+///     inspection.node(play_button_id).unwrap().down,
+///     Some(options_button_id),
+/// );
+/// ```
+pub fn kbgp_inspect(egui_ctx: &egui::Context) -> KbgpInspection {
+    let kbgp = kbgp_get(egui_ctx);
+    let kbgp = kbgp.lock();
+    let common = &kbgp.common;
+
+    let neighbor = |id: egui::Id, direction: KbgpNavDirection| {
+        navigation_target(common, Some(id), direction).filter(|target| *target != id)
+    };
+    let mut nodes = common
+        .nodes
+        .iter()
+        .map(|(id, data)| KbgpNodeInspection {
+            id: *id,
+            rect: data.rect,
+            layer_id: data.layer_id,
+            focus_label_types: data
+                .focus_labels
+                .iter()
+                .map(|(type_name, _)| *type_name)
+                .collect(),
+            focus_labels: data
+                .focus_labels
+                .iter()
+                .map(|(_, label)| label.clone())
+                .collect(),
+            enabled: data.enabled,
+            skipped: data.is_skipped(common.disabled_widgets),
+            up: neighbor(*id, KbgpNavDirection::Up),
+            down: neighbor(*id, KbgpNavDirection::Down),
+            left: neighbor(*id, KbgpNavDirection::Left),
+            right: neighbor(*id, KbgpNavDirection::Right),
+        })
+        .collect::<Vec<_>>();
+    nodes.sort_by(|a, b| {
+        (a.rect.min.y, a.rect.min.x)
+            .partial_cmp(&(b.rect.min.y, b.rect.min.x))
+            .unwrap()
+    });
+
+    let state = match &kbgp.state {
+        KbgpState::Navigation(state) => KbgpStateInspection::Navigation {
+            pending_release: match &state.pending_release_state {
+                PendingReleaseState::Idle => KbgpPendingReleaseInspection::Idle,
+                PendingReleaseState::NodeHeld {
+                    id, is_user_action, ..
+                } => KbgpPendingReleaseInspection::NodeHeld {
+                    id: *id,
+                    is_user_action: *is_user_action,
+                },
+                PendingReleaseState::NodeHoldReleased { id, .. } => {
+                    KbgpPendingReleaseInspection::NodeHoldReleased { id: *id }
+                }
+                PendingReleaseState::GloballyHeld { .. } => {
+                    KbgpPendingReleaseInspection::GloballyHeld
+                }
+                PendingReleaseState::GlobalHoldReleased { .. } => {
+                    KbgpPendingReleaseInspection::GlobalHoldReleased
+                }
+                PendingReleaseState::Invalidated { .. } => {
+                    KbgpPendingReleaseInspection::Invalidated
+                }
+            },
+        },
+        KbgpState::PendingInput(_) => KbgpStateInspection::PendingInput,
+    };
+
+    KbgpInspection {
+        nodes,
+        focused: egui_ctx.memory(|memory| memory.focused()),
+        state,
+    }
+}
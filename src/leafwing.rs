@@ -0,0 +1,96 @@
+//! Integration with leafwing-input-manager.
+//!
+//! Requires the `leafwing` feature.
+
+use bevy::platform::collections::HashMap;
+use leafwing_input_manager::prelude::{
+    ActionState, Actionlike, ButtonlikeChord, GamepadControlDirection, MouseMoveDirection,
+    MouseScrollDirection,
+};
+
+use crate::{KbgpInput, KbgpNavCommand, KbgpPrepareNavigation};
+
+impl KbgpPrepareNavigation {
+    /// Navigate the UI with the actions of a leafwing-input-manager `ActionState`.
+    ///
+    /// Applies the command bound to each pressed action, like
+    /// [`navigate_by_actions`](Self::navigate_by_actions):
+    ///
+    /// ```no_run
+    /// use bevy::platform::collections::HashMap;
+    /// use bevy::prelude::*;
+    /// use bevy_egui::EguiContexts;
+    /// use bevy_egui_kbgp::prelude::*;
+    /// use bevy_egui_kbgp::{kbgp_prepare, KbgpPrepare};
+    /// use leafwing_input_manager::prelude::*;
+    ///
+    /// #[derive(Actionlike, Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+    /// enum MenuAction {
+    ///     Up,
+    ///     Down,
+    ///     Select,
+    /// }
+    ///
+    /// fn prepare_system(
+    ///     mut egui_context: EguiContexts,
+    ///     action_state: Res<ActionState<MenuAction>>,
+    /// ) -> Result {
+    ///     let binding: HashMap<MenuAction, KbgpNavCommand> = [
+    ///         (MenuAction::Up, KbgpNavCommand::NavigateUp),
+    ///         (MenuAction::Down, KbgpNavCommand::NavigateDown),
+    ///         (MenuAction::Select, KbgpNavCommand::Click),
+    ///     ]
+    ///     .into_iter()
+    ///     .collect();
+    ///     kbgp_prepare(egui_context.ctx_mut()?, |prp| {
+    ///         if let KbgpPrepare::Navigation(prp) = prp {
+    ///             prp.navigate_by_action_state(&action_state, &binding);
+    ///         }
+    ///     });
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn navigate_by_action_state<A: Actionlike>(
+        &mut self,
+        action_state: &ActionState<A>,
+        binding: &HashMap<A, KbgpNavCommand>,
+    ) {
+        self.navigate_by_actions(binding, |action| action_state.pressed(action));
+    }
+}
+
+impl KbgpInput {
+    /// Convert a chord (e.g. from
+    /// [`kbgp_pending_chord`](crate::KbgpEguiResponseExt::kbgp_pending_chord)) to a
+    /// leafwing-input-manager input, for binding it in an `InputMap`.
+    ///
+    /// leafwing-input-manager binds gamepad inputs to whichever gamepad the `InputMap` is set to,
+    /// so the gamepad the inputs were captured from is not part of the result.
+    pub fn leafwing_chord(chord: impl Iterator<Item = Self>) -> ButtonlikeChord {
+        chord.fold(ButtonlikeChord::default(), |leafwing_chord, input| {
+            input.add_to_leafwing_chord(leafwing_chord)
+        })
+    }
+
+    fn add_to_leafwing_chord(&self, chord: ButtonlikeChord) -> ButtonlikeChord {
+        match self {
+            KbgpInput::Keyboard(key) => chord.with(*key),
+            KbgpInput::MouseButton(button) => chord.with(*button),
+            KbgpInput::MouseWheelUp => chord.with(MouseScrollDirection::UP),
+            KbgpInput::MouseWheelDown => chord.with(MouseScrollDirection::DOWN),
+            KbgpInput::MouseWheelLeft => chord.with(MouseScrollDirection::LEFT),
+            KbgpInput::MouseWheelRight => chord.with(MouseScrollDirection::RIGHT),
+            KbgpInput::MouseMotionUp => chord.with(MouseMoveDirection::UP),
+            KbgpInput::MouseMotionDown => chord.with(MouseMoveDirection::DOWN),
+            KbgpInput::MouseMotionLeft => chord.with(MouseMoveDirection::LEFT),
+            KbgpInput::MouseMotionRight => chord.with(MouseMoveDirection::RIGHT),
+            KbgpInput::GamepadAxisPositive(_, axis) => {
+                chord.with(GamepadControlDirection::positive(*axis))
+            }
+            KbgpInput::GamepadAxisNegative(_, axis) => {
+                chord.with(GamepadControlDirection::negative(*axis))
+            }
+            KbgpInput::GamepadButton(_, button) => chord.with(*button),
+        }
+    }
+}
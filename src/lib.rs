@@ -5,9 +5,11 @@
 //! * Use [the extension methods](crate::KbgpEguiResponseExt) on the egui widgets to add KBGP's
 //!   functionality.
 //! * Call [`ui.kbgp_clear_input`](crate::KbgpEguiUiCtxExt::kbgp_clear_input) when triggering a
-//!   state transition as a response to a click on an egui widget. To control the focus in the new
+//!   state transition as a response to a click on an egui widget (or, with the `states` feature,
+//!   add `KbgpStatesPlugin` to do it automatically for Bevy states). To control the focus in the new
 //!   state, use [`kbgp_focus_label`](KbgpEguiResponseExt::kbgp_focus_label) (and
-//!   [`kbgp_set_focus_label`](KbgpEguiUiCtxExt::kbgp_set_focus_label)) - otherwise egui will pick
+//!   [`kbgp_set_focus_label`](KbgpEguiUiCtxExt::kbgp_set_focus_label)) or
+//!   [`kbgp_remember_focus`](KbgpEguiUiCtxExt::kbgp_remember_focus) - otherwise egui will pick
 //!   the widget to focus on (or elect to drop the focus entirely)
 //! * To set special actions, see [the example here](crate::KbgpNavCommand::user). To avoid having
 //!   to deal with both Bevy's input methods and KBGP's input, it's better to use these actions for
@@ -16,6 +18,11 @@
 //!   `clicked` to register the button presss only when the user releases the key/button. This is
 //!   useful for exiting menus, to avoid having the same key/button that was used to exit the menu
 //!   registered as actual game input.
+//! * With the `bevy_ui` feature, add `KbgpUiPlugin` to navigate between bevy_ui nodes with the
+//!   same bindings.
+//! * With the `leafwing` feature, navigate with leafwing-input-manager actions using
+//!   `navigate_by_action_state`, and convert captured chords to leafwing inputs with
+//!   `KbgpInput::leafwing_chord`.
 //!
 //! ```no_run
 //! use bevy_egui_kbgp::{egui, bevy_egui};
@@ -71,9 +78,14 @@
 //! Both functions have several variants that allow limiting the chords/keys accepted by that
 //! button.
 //!
+//! For analog actions (throttle, camera, etc.) use
+//! [`kbgp_pending_axis`](crate::KbgpEguiResponseExt::kbgp_pending_axis), which captures a whole
+//! axis as a [`KbgpAxisInput`] instead of a button-like [`KbgpInput`].
+//!
 //! By default, mouse wheel input is disabled. The reason is that mouse wheel events are a pain to
 //! deal with, and most third party crates that ease input handling don't support them - so it's
-//! better not to let the player select input that the game is unable to deal with.
+//! better not to let the player select input that the game is unable to deal with. The same goes
+//! for mouse motion, which is also disabled by default.
 
 pub use bevy_egui;
 pub use bevy_egui::egui;
@@ -82,19 +94,70 @@ use bevy::platform::collections::{HashMap, HashSet};
 use bevy::prelude::*;
 use bevy_egui::{EguiContexts, EguiPrimaryContextPass};
 
-use self::navigation::KbgpPrepareNavigation;
-pub use self::navigation::{KbgpNavActivation, KbgpNavBindings, KbgpNavCommand};
-use self::navigation::{KbgpNavigationState, PendingReleaseState};
+pub use self::debug::kbgp_paint_debug_overlay;
+pub use self::formatting::{KbgpDefaultInputFormatter, KbgpInputFormatter};
+pub use self::glyphs::{KbgpControllerFamily, KbgpGlyphAtlas};
+pub use self::input_source::{KbgpInputSourceProvider, KbgpInputSourceProviders};
+pub use self::inspect::{
+    kbgp_inspect, KbgpInspection, KbgpNodeInspection, KbgpPendingReleaseInspection,
+    KbgpStateInspection,
+};
+use self::navigation::{
+    can_keep_focus, first_focusable_node, KbgpNavigationState, PendingReleaseState,
+};
+pub use self::navigation::{
+    KbgpDisabledWidgetPolicy, KbgpNavActivation, KbgpNavBindings, KbgpNavCommand,
+    KbgpPrepareNavigation, KbgpTextEditEscape,
+};
 use self::pending_input::KbgpPendingInputState;
-pub use self::pending_input::{KbgpInputManualHandle, KbgpPreparePendingInput};
-
+pub use self::pending_input::{
+    KbgpInputManualHandle, KbgpPendingInputPrompt, KbgpPendingInputPromptPlacement,
+    KbgpPreparePendingInput,
+};
+#[cfg(feature = "recording")]
+pub use self::recording::{
+    kbgp_is_replaying, kbgp_start_recording, kbgp_start_replay, kbgp_stop_recording,
+    kbgp_take_replay_error, KbgpRecordedCommand, KbgpRecordedFrame, KbgpRecordedInput,
+    KbgpRecordedUserActions, KbgpRecorder, KbgpRecording, KbgpReplayError, KbgpReplayer,
+};
+pub use self::scrolling::KbgpScrollBinding;
+#[cfg(feature = "states")]
+pub use self::states::KbgpStatesPlugin;
+#[cfg(feature = "bevy_ui")]
+pub use self::ui_nodes::{KbgpFocused, KbgpNode, KbgpNodeActivated, KbgpUiPlugin};
+use self::virtual_cursor::VirtualCursorState;
+pub use self::virtual_cursor::{kbgp_take_virtual_cursor_events, KbgpVirtualCursorBinding};
+pub use self::virtual_keyboard::KbgpVirtualKeyboardActions;
+
+mod combo_box;
+mod debug;
+mod formatting;
+mod glyphs;
+mod input_source;
+mod inspect;
+#[cfg(feature = "leafwing")]
+mod leafwing;
 mod navigation;
 mod pending_input;
+#[cfg(feature = "recording")]
+mod recording;
+mod scrolling;
+#[cfg(feature = "states")]
+mod states;
+mod tab_bar;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "bevy_ui")]
+mod ui_nodes;
+mod virtual_cursor;
+mod virtual_keyboard;
 
 pub mod prelude {
     pub use crate::kbgp_prepare;
+    pub use crate::KbgpAxisInput;
     pub use crate::KbgpEguiResponseExt;
     pub use crate::KbgpEguiUiCtxExt;
+    pub use crate::KbgpEguiUiExt;
     pub use crate::KbgpInput;
     pub use crate::KbgpInputSource;
     pub use crate::KbgpNavActivation;
@@ -102,26 +165,73 @@ pub mod prelude {
     pub use crate::KbgpNavCommand;
     pub use crate::KbgpPlugin;
     pub use crate::KbgpSettings;
+    pub use crate::KbgpSystems;
 }
 
 /// Adds KBGP input handling system and [`KbgpSettings`].
+///
+/// The systems are added in the [`KbgpSystems`] sets.
 pub struct KbgpPlugin;
 
 impl Plugin for KbgpPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(KbgpSettings::default());
+        app.init_resource::<KbgpInputSourceProviders>();
+        app.configure_sets(
+            EguiPrimaryContextPass,
+            (KbgpSystems::Intercept, KbgpSystems::Prepare).chain(),
+        );
+        app.configure_sets(
+            PostUpdate,
+            KbgpSystems::PostUi
+                .after(bevy_egui::EguiPostUpdateSet::EndPass)
+                .before(bevy_egui::EguiPostUpdateSet::ProcessOutput),
+        );
         app.add_systems(
             EguiPrimaryContextPass,
-            kbgp_system_default_input.after(bevy_egui::EguiPreUpdateSet::BeginPass),
+            (
+                kbgp_system_intercept.in_set(KbgpSystems::Intercept),
+                kbgp_system_default_input.in_set(KbgpSystems::Prepare),
+            ),
         );
+        app.add_systems(PostUpdate, kbgp_system_post_ui.in_set(KbgpSystems::PostUi));
     }
 }
 
+/// The system sets [`KbgpPlugin`] adds its systems to.
+///
+/// `Intercept` and `Prepare` run, in that order, in bevy_egui's `EguiPrimaryContextPass` schedule.
+/// UI systems that use KBGP should run after them:
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_egui_kbgp::bevy_egui::EguiPrimaryContextPass;
+/// # use bevy_egui_kbgp::prelude::*;
+/// # fn ui_system() {}
+/// App::new()
+///     .add_plugins(KbgpPlugin)
+///     .add_systems(EguiPrimaryContextPass, ui_system.after(KbgpSystems::Prepare));
+/// ```
+///
+/// Systems that feed custom input to KBGP can run before `Prepare` - e.g. to call
+/// [`kbgp_clear_input`](KbgpEguiUiCtxExt::kbgp_clear_input) or to set a focus label.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KbgpSystems {
+    /// Applies the settings that hide input from egui, and the ones that act on the focus before
+    /// the frame's navigation (e.g. [`KbgpSettings::prevent_loss_of_focus`]).
+    Intercept,
+    /// Calls [`kbgp_prepare`] with the input configured in [`KbgpSettings`].
+    Prepare,
+    /// Calls [`kbgp_validate_focus`] after all the UI was drawn. Runs in the `PostUpdate`
+    /// schedule, after bevy_egui ends the pass.
+    PostUi,
+}
+
 /// General configuration resource for KBGP.
 ///
 /// Note: [`KbgpPlugin`] will add the default settings, so custom settings should either be added
 /// after the plugin or modified with a system. The default is to enable everything except the
-/// mouse wheel.
+/// mouse wheel and the mouse motion.
 #[derive(Resource)]
 pub struct KbgpSettings {
     /// Whether or not egui's tab navigation should work
@@ -160,10 +270,37 @@ pub struct KbgpSettings {
     pub allow_mouse_wheel: bool,
     /// Whether or not mouse wheel sideways scrolling is accepted for chords. Defaults to `false`.
     pub allow_mouse_wheel_sideways: bool,
+    /// Whether or not mouse motion is accepted for chords and axes. Defaults to `false`.
+    pub allow_mouse_motion: bool,
+    /// How many pixels the mouse needs to move in a single frame for the motion to be accepted.
+    /// Thresholds below 1 pixel are treated as 1 pixel. See
+    /// [`accept_mouse_motion_event`](KbgpPreparePendingInput::accept_mouse_motion_event).
+    ///
+    /// Default: 10 pixels.
+    pub mouse_motion_threshold: f32,
     /// Whether or not gamepads input is accepted for navigation and for chords.
     pub allow_gamepads: bool,
     /// Input mapping for navigation.
     pub bindings: KbgpNavBindings,
+    /// How navigation treats disabled widgets. Can be overridden per widget with
+    /// [`kbgp_when_disabled`](KbgpEguiResponseExt::kbgp_when_disabled).
+    ///
+    /// Either way, KBGP will not activate disabled widgets. Defaults to
+    /// [`Focusable`](KbgpDisabledWidgetPolicy::Focusable).
+    pub disabled_widgets: KbgpDisabledWidgetPolicy,
+    /// Whether or not to navigate between all the interactive egui widgets, not just the ones
+    /// marked with [`kbgp_navigation`](crate::KbgpEguiResponseExt::kbgp_navigation). Defaults to
+    /// `false`.
+    ///
+    /// Widgets can opt out with
+    /// [`kbgp_exclude_navigation`](crate::KbgpEguiResponseExt::kbgp_exclude_navigation).
+    pub auto_navigation: bool,
+    /// Which keyboard navigation still works while a `TextEdit` has the focus. By default, only
+    /// Tab (if bound) and up/down at the edges of the text navigate away from it.
+    pub text_edit_escape: KbgpTextEditEscape,
+    /// Whether or not to paint the navigation graph on top of the UI. See
+    /// [`kbgp_paint_debug_overlay`]. Defaults to `false`.
+    pub debug_overlay: bool,
 }
 
 impl Default for KbgpSettings {
@@ -177,8 +314,14 @@ impl Default for KbgpSettings {
             allow_mouse_buttons: true,
             allow_mouse_wheel: false,
             allow_mouse_wheel_sideways: false,
+            allow_mouse_motion: false,
+            mouse_motion_threshold: 10.0,
             allow_gamepads: true,
             bindings: Default::default(),
+            disabled_widgets: Default::default(),
+            auto_navigation: false,
+            text_edit_escape: Default::default(),
+            debug_overlay: false,
         }
     }
 }
@@ -193,6 +336,8 @@ pub enum KbgpPrepare<'a> {
 struct Kbgp {
     common: KbgpCommon,
     state: KbgpState,
+    #[cfg(feature = "recording")]
+    recording: recording::KbgpRecordingState,
 }
 
 fn kbgp_get(egui_ctx: &egui::Context) -> std::sync::Arc<egui::mutex::Mutex<Kbgp>> {
@@ -206,8 +351,8 @@ fn kbgp_get(egui_ctx: &egui::Context) -> std::sync::Arc<egui::mutex::Mutex<Kbgp>
 
 /// Must be called every frame, either manually or by using [`KbgpPlugin`].
 ///
-/// Should be called between bevy_egui's input handling system and the system that generates the
-/// UI - so in the `CoreStage::PreUpdate` stage after the `EguiSystem::ProcessInput` label.
+/// Should be called in the egui pass, before the systems that generate the UI. [`KbgpPlugin`]
+/// calls it in [`KbgpSystems::Prepare`].
 ///
 /// The `prepare_dlg` argument is a closure that accepts a [`KbgpPrepare`], and used to:
 ///
@@ -235,6 +380,7 @@ fn kbgp_get(egui_ctx: &egui::Context) -> std::sync::Arc<egui::mutex::Mutex<Kbgp>
 ///                 prp.navigate_keyboard_by_binding(&keys, &settings.bindings.keyboard, true);
 ///                 for (_, gamepad) in gamepads.iter() {
 ///                     prp.navigate_gamepad_by_binding(gamepad, &settings.bindings.gamepad_buttons);
+///                     prp.scroll_gamepad_by_binding(gamepad, &settings.bindings.scroll);
 ///                 }
 ///             }
 ///             KbgpPrepare::PendingInput(prp) => {
@@ -259,22 +405,52 @@ pub fn kbgp_prepare(egui_ctx: &egui::Context, prepare_dlg: impl FnOnce(KbgpPrepa
     for node_data in kbgp.common.nodes.values_mut() {
         node_data.seen_this_frame = false;
     }
-    let Kbgp { common, state } = &mut *kbgp;
+    if !kbgp.common.modal_layer_seen_this_frame {
+        kbgp.common.modal_layer = None;
+    }
+    kbgp.common.modal_layer_seen_this_frame = false;
+    if !kbgp.common.current_menu_seen_this_frame {
+        kbgp.common.current_menu = None;
+    }
+    kbgp.common.current_menu_seen_this_frame = false;
+    let Kbgp {
+        common,
+        state,
+        #[cfg(feature = "recording")]
+        recording,
+    } = &mut *kbgp;
+    let time = egui_ctx.input(|input| input.time);
+    #[cfg(feature = "recording")]
+    let time = recording.frame_time(time);
     match state {
         KbgpState::Navigation(state) => {
-            state.prepare(common, egui_ctx, |prp| {
+            state.prepare(common, egui_ctx, time, |prp| {
+                #[cfg(feature = "recording")]
+                recording
+                    .prepare_navigation(time, prp, |prp| prepare_dlg(KbgpPrepare::Navigation(prp)));
+                #[cfg(not(feature = "recording"))]
                 prepare_dlg(KbgpPrepare::Navigation(prp))
             });
             if let Some(focus_on) = state.focus_on.take() {
                 egui_ctx.memory_mut(|memory| memory.request_focus(focus_on));
             }
+            state.restore_focus = None;
+            state.focus_restored = false;
+            common.focused_at_prepare = egui_ctx.memory(|memory| memory.focused());
             state.focus_label = state.next_frame_focus_label.take();
             if common.nodes.is_empty() && state.focus_label.is_none() {
                 state.focus_label = Some(Box::new(KbgpInitialFocusLabel));
             }
         }
         KbgpState::PendingInput(state) => {
-            state.prepare(common, egui_ctx, |prp| {
+            // The player enters input with the gamepad, not with the pointer.
+            common.virtual_cursor.deactivate();
+            state.prepare(time, |prp| {
+                #[cfg(feature = "recording")]
+                recording.prepare_pending_input(time, prp, |prp| {
+                    prepare_dlg(KbgpPrepare::PendingInput(prp))
+                });
+                #[cfg(not(feature = "recording"))]
                 prepare_dlg(KbgpPrepare::PendingInput(prp))
             });
             if common.nodes.is_empty() {
@@ -282,6 +458,44 @@ pub fn kbgp_prepare(egui_ctx: &egui::Context, prepare_dlg: impl FnOnce(KbgpPrepa
             }
         }
     }
+    kbgp.common.excluded_nodes.clear();
+}
+
+/// Move the focus away from a widget that cannot keep it.
+///
+/// Should be called after the UI was drawn and the egui pass ended - [`KbgpPlugin`] calls it in
+/// [`KbgpSystems::PostUi`]. If the focused widget was marked for navigation but is skipped (see
+/// [`KbgpDisabledWidgetPolicy::Skip`]) or is outside the modal layer, the focus moves to the first
+/// widget navigation can reach. The same goes for the widget
+/// [`kbgp_prevent_loss_of_focus`] would restore the focus to, if it was not drawn this frame.
+/// Widgets KBGP does not navigate are left alone.
+pub fn kbgp_validate_focus(egui_ctx: &egui::Context) {
+    let kbgp = kbgp_get(egui_ctx);
+    let mut kbgp = kbgp.lock();
+    let Kbgp { common, state, .. } = &mut *kbgp;
+
+    match state {
+        KbgpState::PendingInput(_) => {}
+        KbgpState::Navigation(state) => {
+            let is_invalid = |id: &egui::Id| {
+                common
+                    .nodes
+                    .get(id)
+                    .is_some_and(|data| !can_keep_focus(common, egui_ctx, *id, data))
+            };
+            let focused = egui_ctx.memory(|memory| memory.focused());
+            if let Some(focused) = focused.filter(is_invalid) {
+                let target = first_focusable_node(common, egui_ctx);
+                egui_ctx.memory_mut(|memory| match target {
+                    Some(target) => memory.request_focus(target),
+                    None => memory.surrender_focus(focused),
+                });
+            }
+            if state.last_focus.as_ref().is_some_and(is_invalid) {
+                state.last_focus = first_focusable_node(common, egui_ctx);
+            }
+        }
+    }
 }
 
 /// Cancel's any tab-based navigation egui did in its `BeginFrame`.
@@ -344,7 +558,7 @@ pub fn kbgp_focus_on_mouse_movement(egui_ctx: &egui::Context) {
     let kbgp = kbgp_get(egui_ctx);
     let mut kbgp = kbgp.lock();
 
-    let Kbgp { common, state } = &mut *kbgp;
+    let Kbgp { common, state, .. } = &mut *kbgp;
 
     match state {
         KbgpState::PendingInput(_) => {}
@@ -366,6 +580,24 @@ pub fn kbgp_focus_on_mouse_movement(egui_ctx: &egui::Context) {
     }
 }
 
+/// System that applies the settings that act on egui before KBGP prepares the frame.
+fn kbgp_system_intercept(mut egui_context: EguiContexts, settings: Res<KbgpSettings>) -> Result {
+    let egui_ctx = egui_context.ctx_mut()?;
+    if settings.disable_default_navigation {
+        kbgp_intercept_default_navigation(egui_ctx);
+    }
+    if settings.disable_default_activation {
+        kbgp_intercept_default_activation(egui_ctx);
+    }
+    if settings.prevent_loss_of_focus {
+        kbgp_prevent_loss_of_focus(egui_ctx);
+    }
+    if settings.focus_on_mouse_movement {
+        kbgp_focus_on_mouse_movement(egui_ctx);
+    }
+    Ok(())
+}
+
 /// System that operates KBGP with the default input scheme.
 ///
 /// * Keyboard:
@@ -375,31 +607,54 @@ pub fn kbgp_focus_on_mouse_movement(egui_ctx: &egui::Context) {
 ///   * DPad - navigation.
 ///   * Left stick - navigation.
 ///   * South face button (depends on model - usually X or A): widget activation.
+/// * The providers registered in [`KbgpInputSourceProviders`].
 #[allow(clippy::too_many_arguments)]
-fn kbgp_system_default_input(
+pub(crate) fn kbgp_system_default_input(
     mut egui_context: EguiContexts,
     settings: Res<KbgpSettings>,
     keys: Res<ButtonInput<KeyCode>>,
     mouse_buttons: Res<ButtonInput<MouseButton>>,
     mut mouse_wheel_events: EventReader<bevy::input::mouse::MouseWheel>,
+    mut mouse_motion_events: EventReader<bevy::input::mouse::MouseMotion>,
+    mut gamepad_connection_events: EventReader<bevy::input::gamepad::GamepadConnectionEvent>,
     gamepads: Query<(Entity, &Gamepad)>,
+    primary_context: Query<Entity, With<bevy_egui::PrimaryEguiContext>>,
+    mut egui_input_events: EventWriter<bevy_egui::input::EguiInputEvent>,
+    mut input_source_providers: ResMut<KbgpInputSourceProviders>,
 ) -> Result {
     let egui_ctx = egui_context.ctx_mut()?;
-    if settings.disable_default_navigation {
-        kbgp_intercept_default_navigation(egui_ctx);
-    }
-    if settings.disable_default_activation {
-        kbgp_intercept_default_activation(egui_ctx);
-    }
-    if settings.prevent_loss_of_focus {
-        kbgp_prevent_loss_of_focus(egui_ctx);
+    {
+        let kbgp = kbgp_get(egui_ctx);
+        let mut kbgp = kbgp.lock();
+        // Bevy reuses the entity when the gamepad reconnects, which may be a different controller.
+        for event in gamepad_connection_events.read() {
+            if event.disconnected() {
+                kbgp.common.controller_families.remove(&event.gamepad);
+            }
+            if let Some(formatter) = std::sync::Arc::get_mut(&mut kbgp.common.input_formatter) {
+                formatter.learn_gamepad_connection(event);
+            }
+        }
     }
-    if settings.focus_on_mouse_movement {
-        kbgp_focus_on_mouse_movement(egui_ctx);
+    if settings.allow_gamepads {
+        let kbgp = kbgp_get(egui_ctx);
+        let mut kbgp = kbgp.lock();
+        for (gamepad_entity, gamepad) in gamepads.iter() {
+            kbgp.common
+                .controller_families
+                .entry(gamepad_entity)
+                .or_insert_with(|| KbgpControllerFamily::detect(gamepad));
+        }
     }
 
     kbgp_prepare(egui_ctx, |prp| match prp {
         KbgpPrepare::Navigation(prp) => {
+            prp.disabled_widgets = settings.disabled_widgets;
+            prp.auto_navigation = settings.auto_navigation;
+            prp.text_edit_escape = settings.text_edit_escape.clone();
+            // Only pending input uses mouse motion. Skipping it while navigating makes sure motion
+            // from before the player was prompted will not be captured.
+            mouse_motion_events.clear();
             if settings.allow_keyboard {
                 prp.navigate_keyboard_by_binding(
                     &keys,
@@ -410,8 +665,11 @@ fn kbgp_system_default_input(
             if settings.allow_gamepads {
                 for (_, gamepad) in gamepads.iter() {
                     prp.navigate_gamepad_by_binding(gamepad, &settings.bindings.gamepad_buttons);
+                    prp.scroll_gamepad_by_binding(gamepad, &settings.bindings.scroll);
+                    prp.move_virtual_cursor_by_binding(gamepad, &settings.bindings.virtual_cursor);
                 }
             }
+            input_source_providers.navigation(prp);
         }
         KbgpPrepare::PendingInput(prp) => {
             if settings.allow_keyboard {
@@ -429,19 +687,93 @@ fn kbgp_system_default_input(
                     );
                 }
             }
+            if settings.allow_mouse_motion {
+                for event in mouse_motion_events.read() {
+                    prp.accept_mouse_motion_event(event, settings.mouse_motion_threshold);
+                }
+            }
             if settings.allow_gamepads {
                 for (gamepad_entity, gamepad) in gamepads.iter() {
                     prp.accept_gamepad_input(gamepad_entity, gamepad);
                 }
             }
+            input_source_providers.pending_input(prp);
         }
     });
+    if settings.debug_overlay {
+        kbgp_paint_debug_overlay(egui_ctx);
+    }
+    // egui has already processed this frame's input, so the pointer will move in the next frame.
+    if let Ok(context) = primary_context.single() {
+        for event in kbgp_take_virtual_cursor_events(egui_ctx) {
+            egui_input_events.write(bevy_egui::input::EguiInputEvent { context, event });
+        }
+    }
+    Ok(())
+}
+
+fn kbgp_system_post_ui(mut egui_context: EguiContexts) -> Result {
+    kbgp_validate_focus(egui_context.ctx_mut()?);
     Ok(())
 }
 
-#[derive(Default)]
 struct KbgpCommon {
     nodes: HashMap<egui::Id, NodeData>,
+    input_formatter: std::sync::Arc<dyn KbgpInputFormatter>,
+    glyph_atlas: Option<std::sync::Arc<KbgpGlyphAtlas>>,
+    controller_families: HashMap<Entity, KbgpControllerFamily>,
+    pending_input_prompt: KbgpPendingInputPrompt,
+    disabled_widgets: KbgpDisabledWidgetPolicy,
+    auto_navigation: bool,
+    text_edit_escape: KbgpTextEditEscape,
+    excluded_nodes: HashSet<egui::Id>,
+    // egui refuses to focus disabled widgets, so KBGP needs to remember which one it focused.
+    focused_at_prepare: Option<egui::Id>,
+    // Set by popups (like combo boxes) to keep the navigation inside them while they are open.
+    modal_layer: Option<egui::LayerId>,
+    modal_layer_seen_this_frame: bool,
+    // Set by `kbgp_remember_focus`, to detect when a different menu is shown.
+    current_menu: Option<egui::Id>,
+    current_menu_seen_this_frame: bool,
+    // The last widget focused in each menu passed to `kbgp_remember_focus`.
+    menus_last_focus: HashMap<egui::Id, egui::Id>,
+    // The menus passed to `kbgp_push_menu`, from the root menu to the current one.
+    menu_stack: Vec<(egui::Id, std::sync::Arc<dyn std::any::Any + Send + Sync>)>,
+    // Kept across state changes, so that waiting for input will not lose the toggle or leave the
+    // pointer pressed.
+    virtual_cursor: VirtualCursorState,
+}
+
+impl Default for KbgpCommon {
+    fn default() -> Self {
+        Self {
+            nodes: Default::default(),
+            input_formatter: std::sync::Arc::new(KbgpDefaultInputFormatter::default()),
+            glyph_atlas: None,
+            controller_families: Default::default(),
+            pending_input_prompt: Default::default(),
+            disabled_widgets: Default::default(),
+            auto_navigation: false,
+            text_edit_escape: Default::default(),
+            excluded_nodes: Default::default(),
+            focused_at_prepare: None,
+            modal_layer: None,
+            modal_layer_seen_this_frame: false,
+            current_menu: None,
+            current_menu_seen_this_frame: false,
+            menus_last_focus: Default::default(),
+            menu_stack: Vec::new(),
+            virtual_cursor: Default::default(),
+        }
+    }
+}
+
+impl KbgpCommon {
+    fn controller_family(&self, gamepad: Option<Entity>) -> KbgpControllerFamily {
+        gamepad
+            .and_then(|gamepad| self.controller_families.get(&gamepad).copied())
+            .unwrap_or_default()
+    }
 }
 
 enum KbgpState {
@@ -458,12 +790,56 @@ impl Default for KbgpState {
 #[derive(Debug)]
 struct NodeData {
     rect: egui::Rect,
+    layer_id: egui::LayerId,
+    focus_labels: Vec<(
+        &'static str,
+        std::sync::Arc<dyn std::any::Any + Send + Sync>,
+    )>,
+    enabled: bool,
+    disabled_policy: Option<KbgpDisabledWidgetPolicy>,
+    adjustable: bool,
+    virtual_keyboard: bool,
+    virtual_cursor: bool,
     seen_this_frame: bool,
 }
 
+impl NodeData {
+    /// A node drawn this frame, with none of the optional KBGP behaviors.
+    fn new(rect: egui::Rect, layer_id: egui::LayerId, enabled: bool) -> Self {
+        Self {
+            rect,
+            layer_id,
+            focus_labels: Vec::new(),
+            enabled,
+            disabled_policy: None,
+            adjustable: false,
+            virtual_keyboard: false,
+            virtual_cursor: false,
+            seen_this_frame: true,
+        }
+    }
+
+    fn is_skipped(&self, default_policy: KbgpDisabledWidgetPolicy) -> bool {
+        !self.enabled
+            && self.disabled_policy.unwrap_or(default_policy) == KbgpDisabledWidgetPolicy::Skip
+    }
+}
+
 #[derive(PartialEq)]
 struct KbgpInitialFocusLabel;
 
+fn hold_focus_on_disabled_widget(response: &egui::Response) {
+    response.request_focus();
+    // egui does not paint focus on disabled widgets, so it needs to be painted manually.
+    let visuals = &response.ctx.style().visuals;
+    response.ctx.layer_painter(response.layer_id).rect_stroke(
+        response.rect,
+        visuals.widgets.noninteractive.corner_radius,
+        visuals.selection.stroke,
+        egui::StrokeKind::Outside,
+    );
+}
+
 /// Extensions for egui's `Response` to activate KBGP's functionality.
 ///
 /// ```no_run
@@ -486,6 +862,8 @@ pub trait KbgpEguiResponseExt: Sized {
     ///
     /// This will only happen if `kbgp_set_focus_label` was called in the previous frame. A single
     /// widget can be marked with multiple labels by calling `kbgp_focus_label` multiple times.
+    /// Labels applied after [`kbgp_navigation`](Self::kbgp_navigation) can also be checked with
+    /// [`kbgp_inspect`](crate::kbgp_inspect).
     ///
     /// ```no_run
     /// # use bevy_egui_kbgp::egui;
@@ -513,7 +891,7 @@ pub trait KbgpEguiResponseExt: Sized {
     ///     ui.kbgp_set_focus_label(FocusLabel::Left);
     /// }
     /// ```
-    fn kbgp_focus_label<T: 'static + PartialEq<T>>(self, label: T) -> Self;
+    fn kbgp_focus_label<T: 'static + Send + Sync + PartialEq<T>>(self, label: T) -> Self;
 
     /// When the UI is first created, focus on this widget.
     ///
@@ -528,6 +906,109 @@ pub trait KbgpEguiResponseExt: Sized {
     /// Navigate to and from this widget.
     fn kbgp_navigation(self) -> Self;
 
+    /// Use left and right to change the value of this widget, instead of navigating away from it.
+    ///
+    /// Meant for sliders and drag values. Must be called after
+    /// [`kbgp_navigation`](Self::kbgp_navigation), and with the same value and range the widget
+    /// was created with. Each left/right press changes the value by `step`, and holding them
+    /// accelerates the change. The value is kept within `range`. The new value is shown in the next
+    /// frame, and the response is marked as changed. Up and down still navigate.
+    ///
+    /// While adjusting, KBGP removes the left/right arrow key events, so that the widget will not
+    /// change the value on its own as well.
+    ///
+    /// ```no_run
+    /// # use bevy_egui_kbgp::egui;
+    /// # use bevy_egui_kbgp::prelude::*;
+    /// # let ui: egui::Ui = todo!();
+    /// # let mut volume = 0.5;
+    /// ui.add(egui::Slider::new(&mut volume, 0.0..=1.0))
+    ///     .kbgp_navigation()
+    ///     .kbgp_adjustable(&mut volume, 0.0..=1.0, 0.05);
+    /// ```
+    fn kbgp_adjustable<N: egui::emath::Numeric>(
+        self,
+        value: &mut N,
+        range: std::ops::RangeInclusive<N>,
+        step: N,
+    ) -> Self;
+
+    /// Open an on-screen keyboard when this widget is activated with KBGP, so that the text can be
+    /// entered with a gamepad.
+    ///
+    /// Meant for `TextEdit`s. Must be called after [`kbgp_navigation`](Self::kbgp_navigation), and
+    /// with the same text the widget was created with. While the keyboard is open the `TextEdit`
+    /// keeps the focus, navigation moves between the keys instead of between widgets, and the
+    /// pressed keys are sent to the `TextEdit` as egui text events. The keyboard is closed with
+    /// its Done and Cancel keys (Cancel restores the text), when the `TextEdit` loses the focus, or
+    /// with the user actions in `actions`.
+    ///
+    /// ```no_run
+    /// # use bevy_egui_kbgp::egui;
+    /// # use bevy_egui_kbgp::prelude::*;
+    /// # use bevy_egui_kbgp::KbgpVirtualKeyboardActions;
+    /// # let ui: egui::Ui = todo!();
+    /// # #[derive(Clone, PartialEq)]
+    /// # enum MyUserAction { Back, Backspace }
+    /// # let mut name = String::new();
+    /// ui.text_edit_singleline(&mut name)
+    ///     .kbgp_navigation()
+    ///     .kbgp_virtual_keyboard(
+    ///         &mut name,
+    ///         &KbgpVirtualKeyboardActions {
+    ///             cancel: Some(MyUserAction::Back),
+    ///             backspace: Some(MyUserAction::Backspace),
+    ///             ..Default::default()
+    ///         },
+    ///     );
+    /// ```
+    fn kbgp_virtual_keyboard<T: 'static + Clone + PartialEq>(
+        self,
+        text: &mut String,
+        actions: &KbgpVirtualKeyboardActions<T>,
+    ) -> Self;
+
+    /// Activate the virtual cursor while this widget has the focus.
+    ///
+    /// Meant for widgets that are not button-based - maps, free-form canvases, plots. Must be
+    /// called after [`kbgp_navigation`](Self::kbgp_navigation). While the widget has the focus, the
+    /// virtual cursor is shown inside it and moved with the stick bound in
+    /// [`KbgpNavBindings::virtual_cursor`], and activation presses the pointer instead of
+    /// activating the widget. The d-pad still navigates away from the widget.
+    ///
+    /// ```no_run
+    /// # use bevy_egui_kbgp::egui;
+    /// # use bevy_egui_kbgp::prelude::*;
+    /// # let ui: egui::Ui = todo!();
+    /// let (response, painter) =
+    ///     ui.allocate_painter(egui::vec2(300.0, 200.0), egui::Sense::click_and_drag());
+    /// let response = response.kbgp_navigation().kbgp_virtual_cursor();
+    /// if let Some(pos) = response.interact_pointer_pos() {
+    ///     painter.circle_filled(pos, 2.0, egui::Color32::WHITE);
+    /// }
+    /// ```
+    fn kbgp_virtual_cursor(self) -> Self;
+
+    /// Exclude this widget from navigation when
+    /// [`KbgpSettings::auto_navigation`] is set.
+    fn kbgp_exclude_navigation(self) -> Self;
+
+    /// Override [`KbgpSettings::disabled_widgets`] for this widget.
+    ///
+    /// Must be called after [`kbgp_navigation`](Self::kbgp_navigation):
+    ///
+    /// ```no_run
+    /// # use bevy_egui_kbgp::egui;
+    /// # use bevy_egui_kbgp::prelude::*;
+    /// # use bevy_egui_kbgp::KbgpDisabledWidgetPolicy;
+    /// # let ui: egui::Ui = todo!();
+    /// # let has_save_file = false;
+    /// ui.add_enabled(has_save_file, egui::Button::new("Continue"))
+    ///     .kbgp_navigation()
+    ///     .kbgp_when_disabled(KbgpDisabledWidgetPolicy::Skip);
+    /// ```
+    fn kbgp_when_disabled(self, policy: KbgpDisabledWidgetPolicy) -> Self;
+
     /// Check if the player pressed a user action button while focused on this widget.
     ///
     /// ```no_run
@@ -685,6 +1166,47 @@ pub trait KbgpEguiResponseExt: Sized {
         pred: impl FnMut(&HashSet<KbgpInput>, KbgpInput) -> bool,
     ) -> Option<HashSet<KbgpInput>>;
 
+    /// Accept a whole analog axis from this widget.
+    ///
+    /// The axis is selected once the player tilts it at least `threshold` (in absolute value), and
+    /// is returned when the player lets go of it. Axes that were already tilted when the widget
+    /// was activated are ignored until they return to rest.
+    ///
+    /// Axes at rest may not report exactly zero, so a `threshold` below 0.2 is treated as 0.2.
+    ///
+    /// Must be called on widgets that had
+    /// [`kbgp_navigation`](crate::KbgpEguiResponseExt::kbgp_navigation) called on them.
+    ///
+    /// ```no_run
+    /// # use bevy_egui_kbgp::egui;
+    /// # use bevy_egui_kbgp::prelude::*;
+    /// # let ui: egui::Ui = todo!();
+    /// # let mut throttle_axis: Option<KbgpAxisInput> = None;
+    /// if let Some(new_throttle_axis) = ui
+    ///     .button("Set throttle axis")
+    ///     .kbgp_navigation()
+    ///     .kbgp_pending_axis(0.5)
+    /// {
+    ///     throttle_axis = Some(new_throttle_axis);
+    /// }
+    /// ```
+    fn kbgp_pending_axis(&self, threshold: f32) -> Option<KbgpAxisInput>;
+
+    /// Accept a whole analog axis from this widget, limited to a specific input source.
+    fn kbgp_pending_axis_of_source(
+        &self,
+        threshold: f32,
+        source: KbgpInputSource,
+    ) -> Option<KbgpAxisInput>;
+
+    /// Accept a whole analog axis from this widget, with the ability to filter which axes to
+    /// accept.
+    fn kbgp_pending_axis_vetted(
+        &self,
+        threshold: f32,
+        pred: impl FnMut(KbgpAxisInput) -> bool,
+    ) -> Option<KbgpAxisInput>;
+
     /// Helper for manually implementing custom methods for input-setting
     ///
     /// Inside the delegate, one would usually:
@@ -695,6 +1217,10 @@ pub trait KbgpEguiResponseExt: Sized {
     ///   [`show_current_chord`](crate::pending_input::KbgpInputManualHandle::show_current_chord)
     ///   to show the tooltip, or generate some other visual cue.
     /// * Return `None` if the player did not finish entering the input.
+    ///
+    /// If the [`timeout`](KbgpPendingInputPrompt::timeout) set with
+    /// [`kbgp_set_pending_input_prompt`](KbgpEguiUiCtxExt::kbgp_set_pending_input_prompt) passes
+    /// before the delegate returns `Some`, the input setting is cancelled.
     fn kbgp_pending_input_manual<T>(
         &self,
         dlg: impl FnOnce(&Self, KbgpInputManualHandle) -> Option<T>,
@@ -702,14 +1228,14 @@ pub trait KbgpEguiResponseExt: Sized {
 }
 
 impl KbgpEguiResponseExt for egui::Response {
-    fn kbgp_focus_label<T: 'static + PartialEq<T>>(self, label: T) -> Self {
+    fn kbgp_focus_label<T: 'static + Send + Sync + PartialEq<T>>(self, label: T) -> Self {
         let kbgp = kbgp_get(&self.ctx);
         let mut kbgp = kbgp.lock();
         match &mut kbgp.state {
             KbgpState::Navigation(state) => {
                 if let Some(focus_label) = &state.focus_label {
                     if let Some(focus_label) = focus_label.downcast_ref::<T>() {
-                        if focus_label == &label {
+                        if focus_label == &label && !state.focus_restored {
                             state.focus_label = None;
                             state.focus_on = Some(self.id);
                         }
@@ -718,6 +1244,17 @@ impl KbgpEguiResponseExt for egui::Response {
             }
             KbgpState::PendingInput(_) => {}
         }
+        let label = std::sync::Arc::new(label);
+        if let Some(node_data) = kbgp.common.nodes.get_mut(&self.id) {
+            if node_data.seen_this_frame {
+                node_data
+                    .focus_labels
+                    .push((std::any::type_name::<T>(), label.clone()));
+            }
+        }
+        drop(kbgp);
+        #[cfg(feature = "testing")]
+        testing::record_focus_label(self.id, label);
         self
     }
 
@@ -737,18 +1274,102 @@ impl KbgpEguiResponseExt for egui::Response {
     fn kbgp_navigation(self) -> Self {
         let kbgp = kbgp_get(&self.ctx);
         let mut kbgp = kbgp.lock();
-        kbgp.common.nodes.insert(
-            self.id,
-            NodeData {
-                rect: self.rect,
-                seen_this_frame: true,
-            },
-        );
+        let node_data = NodeData::new(self.rect, self.layer_id, self.enabled());
+        if !node_data.enabled
+            && !node_data.is_skipped(kbgp.common.disabled_widgets)
+            && kbgp.common.focused_at_prepare == Some(self.id)
+        {
+            hold_focus_on_disabled_widget(&self);
+        }
+        if let KbgpState::Navigation(state) = &mut kbgp.state {
+            if state.restore_focus == Some(self.id) {
+                state.restore_focus = None;
+                state.focus_restored = true;
+                state.focus_on = Some(self.id);
+            }
+        }
+        kbgp.common.nodes.insert(self.id, node_data);
+        self
+    }
+
+    fn kbgp_adjustable<N: egui::emath::Numeric>(
+        mut self,
+        value: &mut N,
+        range: std::ops::RangeInclusive<N>,
+        step: N,
+    ) -> Self {
+        let kbgp = kbgp_get(&self.ctx);
+        let mut kbgp = kbgp.lock();
+        let Kbgp { common, state, .. } = &mut *kbgp;
+        if let Some(node_data) = common.nodes.get_mut(&self.id) {
+            node_data.adjustable = true;
+        }
+        if let KbgpState::Navigation(state) = state {
+            if let Some(adjustment) = state.adjustment {
+                if self.has_focus() && self.enabled() {
+                    let (start, end) = (range.start().to_f64(), range.end().to_f64());
+                    let adjusted = value.to_f64() + step.to_f64() * adjustment;
+                    // Like egui's sliders, allow ranges that go from high to low.
+                    *value = N::from_f64(adjusted.max(start.min(end)).min(start.max(end)));
+                    self.mark_changed();
+                    self.ctx.request_repaint();
+                }
+            }
+        }
+        self
+    }
+
+    fn kbgp_virtual_keyboard<T: 'static + Clone + PartialEq>(
+        self,
+        text: &mut String,
+        actions: &KbgpVirtualKeyboardActions<T>,
+    ) -> Self {
+        {
+            let kbgp = kbgp_get(&self.ctx);
+            let mut kbgp = kbgp.lock();
+            if let Some(node_data) = kbgp.common.nodes.get_mut(&self.id) {
+                node_data.virtual_keyboard = true;
+            }
+        }
+        virtual_keyboard::show_virtual_keyboard(&self, text, actions);
+        self
+    }
+
+    fn kbgp_virtual_cursor(self) -> Self {
+        let kbgp = kbgp_get(&self.ctx);
+        let mut kbgp = kbgp.lock();
+        if let Some(node_data) = kbgp.common.nodes.get_mut(&self.id) {
+            node_data.virtual_cursor = true;
+        }
+        self
+    }
+
+    fn kbgp_exclude_navigation(self) -> Self {
+        let kbgp = kbgp_get(&self.ctx);
+        let mut kbgp = kbgp.lock();
+        kbgp.common.nodes.remove(&self.id);
+        kbgp.common.excluded_nodes.insert(self.id);
+        self
+    }
+
+    fn kbgp_when_disabled(self, policy: KbgpDisabledWidgetPolicy) -> Self {
+        let kbgp = kbgp_get(&self.ctx);
+        let mut kbgp = kbgp.lock();
+        let focused_at_prepare = kbgp.common.focused_at_prepare;
+        if let Some(node_data) = kbgp.common.nodes.get_mut(&self.id) {
+            node_data.disabled_policy = Some(policy);
+            if !node_data.enabled && focused_at_prepare == Some(self.id) {
+                match policy {
+                    KbgpDisabledWidgetPolicy::Focusable => hold_focus_on_disabled_widget(&self),
+                    KbgpDisabledWidgetPolicy::Skip => self.surrender_focus(),
+                }
+            }
+        }
         self
     }
 
     fn kbgp_user_action<T: 'static + Clone>(&self) -> Option<T> {
-        if self.has_focus() {
+        if self.has_focus() && self.enabled() {
             self.ctx.kbgp_user_action()
         } else {
             None
@@ -784,6 +1405,9 @@ impl KbgpEguiResponseExt for egui::Response {
     }
 
     fn kbgp_click_released(&self) -> bool {
+        if !self.enabled() {
+            return false;
+        }
         let kbgp = kbgp_get(&self.ctx);
         let kbgp = kbgp.lock();
         if let KbgpState::Navigation(state) = &kbgp.state {
@@ -803,7 +1427,7 @@ impl KbgpEguiResponseExt for egui::Response {
     }
 
     fn kbgp_user_action_released<T: 'static + Clone>(&self) -> Option<T> {
-        if self.has_focus() {
+        if self.has_focus() && self.enabled() {
             let kbgp = kbgp_get(&self.ctx);
             let kbgp = kbgp.lock();
             if let KbgpState::Navigation(state) = &kbgp.state {
@@ -827,7 +1451,8 @@ impl KbgpEguiResponseExt for egui::Response {
     ) -> Option<T> {
         let kbgp = kbgp_get(&self.ctx);
         let mut kbgp = kbgp.lock();
-        match &mut kbgp.state {
+        let Kbgp { common, state, .. } = &mut *kbgp;
+        match state {
             KbgpState::Navigation(_) => {
                 if self.clicked() {
                     kbgp.state = KbgpState::PendingInput(KbgpPendingInputState::new(self.id));
@@ -850,9 +1475,13 @@ impl KbgpEguiResponseExt for egui::Response {
                         },
                     )
                 });
-                let handle = KbgpInputManualHandle { state };
+                let handle = KbgpInputManualHandle { state, common };
                 let result = dlg(self, handle);
-                if result.is_some() {
+                let timed_out = common
+                    .pending_input_prompt
+                    .timeout
+                    .is_some_and(|timeout| timeout <= state.elapsed_secs());
+                if result.is_some() || timed_out {
                     kbgp.state = KbgpState::Navigation(KbgpNavigationState::default());
                 }
                 result
@@ -926,10 +1555,46 @@ impl KbgpEguiResponseExt for egui::Response {
             }
         })
     }
+
+    fn kbgp_pending_axis(&self, threshold: f32) -> Option<KbgpAxisInput> {
+        self.kbgp_pending_axis_vetted(threshold, |_| true)
+    }
+
+    fn kbgp_pending_axis_of_source(
+        &self,
+        threshold: f32,
+        source: KbgpInputSource,
+    ) -> Option<KbgpAxisInput> {
+        self.kbgp_pending_axis_vetted(threshold, |axis| axis.get_source() == source)
+    }
+
+    fn kbgp_pending_axis_vetted(
+        &self,
+        threshold: f32,
+        mut pred: impl FnMut(KbgpAxisInput) -> bool,
+    ) -> Option<KbgpAxisInput> {
+        let threshold = threshold.max(pending_input::MIN_AXIS_THRESHOLD);
+        self.kbgp_pending_input_manual(|response, mut hnd| {
+            hnd.process_new_axis(threshold, |hnd, axis| {
+                hnd.received_axis().is_none() && pred(axis)
+            });
+            hnd.show_current_axis(response);
+            let received_axis = hnd.received_axis()?.clone();
+            if hnd
+                .axes_this_frame()
+                .any(|(axis, value)| axis == received_axis && threshold <= value.abs())
+            {
+                None
+            } else {
+                Some(received_axis)
+            }
+        })
+    }
 }
 
 /// Input from the keyboard or from a gamepad.
 #[derive(Hash, PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum KbgpInput {
     Keyboard(KeyCode),
     MouseButton(MouseButton),
@@ -937,49 +1602,44 @@ pub enum KbgpInput {
     MouseWheelDown,
     MouseWheelLeft,
     MouseWheelRight,
+    MouseMotionUp,
+    MouseMotionDown,
+    MouseMotionLeft,
+    MouseMotionRight,
     GamepadAxisPositive(Entity, GamepadAxis),
     GamepadAxisNegative(Entity, GamepadAxis),
     GamepadButton(Entity, GamepadButton),
 }
 
+/// Formats the input with [`KbgpDefaultInputFormatter`].
 impl core::fmt::Display for KbgpInput {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            KbgpInput::Keyboard(key) => write!(f, "{key:?}")?,
-            KbgpInput::MouseButton(MouseButton::Other(button)) => {
-                write!(f, "MouseButton{button:?}")?
-            }
-            KbgpInput::MouseButton(button) => write!(f, "Mouse{button:?}")?,
-            KbgpInput::MouseWheelUp => write!(f, "MouseScrollUp")?,
-            KbgpInput::MouseWheelDown => write!(f, "MouseScrollDown")?,
-            KbgpInput::MouseWheelLeft => write!(f, "MouseScrollLeft")?,
-            KbgpInput::MouseWheelRight => write!(f, "MouseScrollRight")?,
-            KbgpInput::GamepadButton(entity, gamepad_button) => {
-                write!(f, "[{entity}]{gamepad_button:?}")?
-            }
-            KbgpInput::GamepadAxisPositive(entity, gamepad_axis) => {
-                write!(f, "[{entity}]{gamepad_axis:?}")?
-            }
-            KbgpInput::GamepadAxisNegative(entity, gamepad_axis) => {
-                write!(f, "[{entity}]-{gamepad_axis:?}")?
-            }
-        }
-        Ok(())
+        write!(
+            f,
+            "{}",
+            formatting::default_input_formatter().format_input(self)
+        )
     }
 }
 
 impl KbgpInput {
     /// Create a string that describes a chord of multiple inputs.
+    ///
+    /// Uses [`KbgpDefaultInputFormatter`] - use
+    /// [`format_chord_with`](Self::format_chord_with) for other formatters.
     pub fn format_chord(chord: impl Iterator<Item = Self>) -> String {
-        let mut chord_text = String::new();
-        for input in chord {
-            use std::fmt::Write;
-            if !chord_text.is_empty() {
-                write!(&mut chord_text, " & ").unwrap();
-            }
-            write!(&mut chord_text, "{input}").unwrap();
-        }
-        chord_text
+        Self::format_chord_with(formatting::default_input_formatter(), chord)
+    }
+
+    /// Create a string that describes a chord of multiple inputs, using a specific formatter.
+    ///
+    /// The formatter installed in the egui context can be retrieved with
+    /// [`kbgp_input_formatter`](crate::KbgpEguiUiCtxExt::kbgp_input_formatter).
+    pub fn format_chord_with(
+        formatter: &dyn KbgpInputFormatter,
+        mut chord: impl Iterator<Item = Self>,
+    ) -> String {
+        formatter.format_chord(&mut chord)
     }
 
     /// Return the source responsible for this input.
@@ -991,6 +1651,10 @@ impl KbgpInput {
             KbgpInput::MouseWheelDown => KbgpInputSource::KeyboardAndMouse,
             KbgpInput::MouseWheelLeft => KbgpInputSource::KeyboardAndMouse,
             KbgpInput::MouseWheelRight => KbgpInputSource::KeyboardAndMouse,
+            KbgpInput::MouseMotionUp => KbgpInputSource::KeyboardAndMouse,
+            KbgpInput::MouseMotionDown => KbgpInputSource::KeyboardAndMouse,
+            KbgpInput::MouseMotionLeft => KbgpInputSource::KeyboardAndMouse,
+            KbgpInput::MouseMotionRight => KbgpInputSource::KeyboardAndMouse,
             KbgpInput::GamepadAxisPositive(entity, _) => KbgpInputSource::Gamepad(*entity),
             KbgpInput::GamepadAxisNegative(entity, _) => KbgpInputSource::Gamepad(*entity),
             KbgpInput::GamepadButton(entity, _) => KbgpInputSource::Gamepad(*entity),
@@ -998,8 +1662,42 @@ impl KbgpInput {
     }
 }
 
+/// A whole analog axis, for binding analog actions.
+#[derive(Hash, PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KbgpAxisInput {
+    /// Horizontal mouse motion. Positive is right.
+    MouseMotionX,
+    /// Vertical mouse motion. Positive is up.
+    MouseMotionY,
+    GamepadAxis(Entity, GamepadAxis),
+}
+
+/// Formats the axis with [`KbgpDefaultInputFormatter`].
+impl core::fmt::Display for KbgpAxisInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            formatting::default_input_formatter().format_axis(self)
+        )
+    }
+}
+
+impl KbgpAxisInput {
+    /// Return the source responsible for this axis.
+    pub fn get_source(&self) -> KbgpInputSource {
+        match self {
+            KbgpAxisInput::MouseMotionX => KbgpInputSource::KeyboardAndMouse,
+            KbgpAxisInput::MouseMotionY => KbgpInputSource::KeyboardAndMouse,
+            KbgpAxisInput::GamepadAxis(entity, _) => KbgpInputSource::Gamepad(*entity),
+        }
+    }
+}
+
 /// Input from the keyboard or from a gamepad.
 #[derive(Hash, PartialEq, Eq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum KbgpInputSource {
     KeyboardAndMouse,
     Gamepad(Entity),
@@ -1039,6 +1737,76 @@ pub trait KbgpEguiUiCtxExt {
     /// This will only happen on the next frame.
     fn kbgp_set_focus_label<T: 'static + Send + Sync>(&self, label: T);
 
+    /// Remember the focused widget of a menu, and restore it when the menu is shown again.
+    ///
+    /// Should be called every frame the menu is shown, before drawing its widgets. `menu` can be
+    /// any hashable value that identifies the menu - e.g. the value of the Bevy `State` the menu
+    /// is shown in. When the menu is shown after a different menu (or after no menu), the focus
+    /// moves to the widget that was focused when the menu was last shown. If that widget no longer
+    /// exists (or the menu was never shown before), the focus moves to the widget marked with
+    /// [`kbgp_initial_focus`](KbgpEguiResponseExt::kbgp_initial_focus).
+    ///
+    /// A label set with [`kbgp_set_focus_label`](Self::kbgp_set_focus_label) in the previous frame
+    /// takes precedence over the remembered focus.
+    ///
+    /// ```no_run
+    /// # use bevy_egui_kbgp::egui;
+    /// # use bevy_egui_kbgp::prelude::*;
+    /// # let ui: egui::Ui = todo!();
+    /// # #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    /// # enum MenuState { Main, Options }
+    /// # let menu_state = MenuState::Main;
+    /// ui.kbgp_remember_focus(menu_state);
+    /// match menu_state {
+    ///     MenuState::Main => {
+    ///         ui.button("Play").kbgp_navigation().kbgp_initial_focus();
+    ///         ui.button("Options").kbgp_navigation();
+    ///     }
+    ///     MenuState::Options => {
+    ///         ui.button("Audio").kbgp_navigation().kbgp_initial_focus();
+    ///         ui.button("Video").kbgp_navigation();
+    ///     }
+    /// }
+    /// ```
+    fn kbgp_remember_focus(&self, menu: impl std::hash::Hash);
+
+    /// Mark the menu as the current menu in KBGP's menu stack.
+    ///
+    /// Should be called every frame the menu is shown, before drawing its widgets. Entering a menu
+    /// pushes it to the stack, and returning to a menu that is already in the stack pops the menus
+    /// above it. [`KbgpNavCommand::Back`] pops the current menu, and the game should react to it by
+    /// checking [`kbgp_back`](Self::kbgp_back). The focused widget of each menu is remembered and
+    /// restored, like with [`kbgp_remember_focus`](Self::kbgp_remember_focus).
+    ///
+    /// ```no_run
+    /// # use bevy_egui_kbgp::egui;
+    /// # use bevy_egui_kbgp::prelude::*;
+    /// # let ui: egui::Ui = todo!();
+    /// # #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    /// # enum Menu { Main, Options, Audio }
+    /// # let mut menu = Menu::Main;
+    /// ui.kbgp_push_menu(menu);
+    /// if let Some(previous_menu) = ui.kbgp_back::<Menu>() {
+    ///     menu = previous_menu;
+    /// }
+    /// match menu {
+    ///     Menu::Main => {
+    ///         if ui.button("Options").kbgp_navigation().kbgp_initial_focus().clicked() {
+    ///             ui.kbgp_clear_input();
+    ///             menu = Menu::Options;
+    ///         }
+    ///     }
+    ///     // ...
+    /// #   _ => {}
+    /// }
+    /// ```
+    fn kbgp_push_menu<K: 'static + Clone + std::hash::Hash + Send + Sync>(&self, menu: K);
+
+    /// The menu to go back to, if the player pressed [`KbgpNavCommand::Back`] this frame.
+    ///
+    /// `K` must be the type passed to [`kbgp_push_menu`](Self::kbgp_push_menu).
+    fn kbgp_back<K: 'static + Clone>(&self) -> Option<K>;
+
     /// Check if the player pressed a user action button.
     ///
     /// Note that if the focus is on a widget that handles a user action, it will be reported both
@@ -1062,6 +1830,165 @@ pub trait KbgpEguiUiCtxExt {
     /// Similar to [`kbgp_user_action`](Self::kbgp_user_action), but only returns `Some` when the
     /// key/button is released.
     fn kbgp_user_action_released<T: 'static + Clone>(&self) -> Option<T>;
+
+    /// Set the formatter used for presenting input to the player.
+    ///
+    /// This affects the tooltips of the input setting methods, like
+    /// [`kbgp_pending_input`](crate::KbgpEguiResponseExt::kbgp_pending_input). The default is
+    /// [`KbgpDefaultInputFormatter`].
+    fn kbgp_set_input_formatter(&self, formatter: impl KbgpInputFormatter);
+
+    /// The formatter set by [`kbgp_set_input_formatter`](Self::kbgp_set_input_formatter).
+    ///
+    /// ```no_run
+    /// # use bevy_egui_kbgp::egui;
+    /// # use bevy_egui_kbgp::prelude::*;
+    /// # let ui: egui::Ui = todo!();
+    /// # let chord: Vec<KbgpInput> = todo!();
+    /// let formatter = ui.kbgp_input_formatter();
+    /// ui.button(KbgpInput::format_chord_with(formatter.as_ref(), chord.iter().cloned()));
+    /// ```
+    fn kbgp_input_formatter(&self) -> std::sync::Arc<dyn KbgpInputFormatter>;
+
+    /// Set the images used for rendering input.
+    ///
+    /// Once set, the glyphs are used by the input setting tooltips (see
+    /// [`show_current_chord`](KbgpInputManualHandle::show_current_chord)) and can be rendered with
+    /// [`kbgp_input_glyph`](KbgpEguiUiExt::kbgp_input_glyph).
+    fn kbgp_set_glyph_atlas(&self, atlas: KbgpGlyphAtlas);
+
+    /// Override the family of a gamepad, which determines its glyphs.
+    ///
+    /// [`KbgpPlugin`] detects the family of each gamepad with [`KbgpControllerFamily::detect`],
+    /// but only if it was not set manually.
+    fn kbgp_set_controller_family(&self, gamepad: Entity, family: KbgpControllerFamily);
+
+    /// Configure the prompt shown while the player enters input.
+    fn kbgp_set_pending_input_prompt(&self, prompt: KbgpPendingInputPrompt);
+}
+
+/// Extensions for egui's `UI` to render KBGP related widgets.
+pub trait KbgpEguiUiExt {
+    /// Render an input using the glyph atlas set by
+    /// [`kbgp_set_glyph_atlas`](KbgpEguiUiCtxExt::kbgp_set_glyph_atlas).
+    ///
+    /// Falls back to text (using the formatter set by
+    /// [`kbgp_set_input_formatter`](KbgpEguiUiCtxExt::kbgp_set_input_formatter)) if there is no
+    /// glyph for the input.
+    fn kbgp_input_glyph(&mut self, input: &KbgpInput) -> egui::Response;
+
+    /// Render a whole analog axis using the glyph atlas, falling back to text.
+    fn kbgp_axis_glyph(&mut self, axis: &KbgpAxisInput) -> egui::Response;
+
+    /// Render a chord of inputs using the glyph atlas, falling back to text.
+    fn kbgp_chord_glyphs(&mut self, chord: impl Iterator<Item = KbgpInput>) -> egui::Response;
+
+    /// Show a [`egui::ComboBox`] that can be operated with keyboard and gamepad.
+    ///
+    /// The combo button is marked with
+    /// [`kbgp_navigation`](KbgpEguiResponseExt::kbgp_navigation). Clicking it moves the focus to
+    /// the first item of the popup, and navigation stays inside the popup while it is open - there
+    /// is no need to call `kbgp_navigation` on the items. Clicking an item or triggering
+    /// `cancel_action` (see [`kbgp_user_action`](KbgpEguiUiCtxExt::kbgp_user_action)) closes the
+    /// popup and returns the focus to the combo button.
+    ///
+    /// ```no_run
+    /// # use bevy_egui_kbgp::egui;
+    /// # use bevy_egui_kbgp::prelude::*;
+    /// # let ui: egui::Ui = todo!();
+    /// # #[derive(Clone, PartialEq)]
+    /// # enum MyUserAction { Back }
+    /// # let mut selected = 0;
+    /// ui.kbgp_combo_box(
+    ///     egui::ComboBox::from_label("Difficulty").selected_text(format!("{selected}")),
+    ///     MyUserAction::Back,
+    ///     |ui| {
+    ///         for difficulty in 0..3 {
+    ///             ui.selectable_value(&mut selected, difficulty, format!("{difficulty}"));
+    ///         }
+    ///     },
+    /// );
+    /// ```
+    fn kbgp_combo_box<R, T: 'static + Clone + PartialEq>(
+        &mut self,
+        combo_box: egui::ComboBox,
+        cancel_action: T,
+        add_contents: impl FnOnce(&mut egui::Ui) -> R,
+    ) -> egui::InnerResponse<Option<R>>;
+
+    /// Show a row of tabs, with the selected one highlighted.
+    ///
+    /// The tabs are marked with [`kbgp_navigation`](KbgpEguiResponseExt::kbgp_navigation), and
+    /// can be selected by clicking them. [`KbgpNavCommand::PrevTab`] and
+    /// [`KbgpNavCommand::NextTab`] (LB/RB and Q/E by default) cycle the tabs from anywhere, and
+    /// move the focus to the widget that was last focused while the new tab was selected - or to
+    /// the tab itself if there is no such widget. Only widgets inside this `Ui` count, so the page
+    /// of each tab should be drawn in the same `Ui` as the tab bar. The response is marked as
+    /// changed when the selected tab changes.
+    ///
+    /// ```no_run
+    /// # use bevy_egui_kbgp::egui;
+    /// # use bevy_egui_kbgp::prelude::*;
+    /// # let ui: egui::Ui = todo!();
+    /// # #[derive(Clone, PartialEq)]
+    /// # enum SettingsTab { Video, Audio, Controls }
+    /// # let mut tab = SettingsTab::Video;
+    /// ui.kbgp_tab_bar(
+    ///     &mut tab,
+    ///     [
+    ///         (SettingsTab::Video, "Video"),
+    ///         (SettingsTab::Audio, "Audio"),
+    ///         (SettingsTab::Controls, "Controls"),
+    ///     ],
+    /// );
+    /// match tab {
+    ///     SettingsTab::Video => { /* ... */ }
+    ///     SettingsTab::Audio => { /* ... */ }
+    ///     SettingsTab::Controls => { /* ... */ }
+    /// }
+    /// ```
+    fn kbgp_tab_bar<T: Clone + PartialEq>(
+        &mut self,
+        selected: &mut T,
+        tabs: impl IntoIterator<Item = (T, impl Into<egui::WidgetText>)>,
+    ) -> egui::Response;
+}
+
+impl KbgpEguiUiExt for egui::Ui {
+    fn kbgp_input_glyph(&mut self, input: &KbgpInput) -> egui::Response {
+        let kbgp = kbgp_get(self.ctx());
+        let kbgp = kbgp.lock();
+        glyphs::show_input_glyph(self, &kbgp.common, input)
+    }
+
+    fn kbgp_axis_glyph(&mut self, axis: &KbgpAxisInput) -> egui::Response {
+        let kbgp = kbgp_get(self.ctx());
+        let kbgp = kbgp.lock();
+        glyphs::show_axis_glyph(self, &kbgp.common, axis)
+    }
+
+    fn kbgp_chord_glyphs(&mut self, chord: impl Iterator<Item = KbgpInput>) -> egui::Response {
+        let kbgp = kbgp_get(self.ctx());
+        let kbgp = kbgp.lock();
+        glyphs::show_chord_glyphs(self, &kbgp.common, chord)
+    }
+
+    fn kbgp_combo_box<R, T: 'static + Clone + PartialEq>(
+        &mut self,
+        combo_box: egui::ComboBox,
+        cancel_action: T,
+        add_contents: impl FnOnce(&mut egui::Ui) -> R,
+    ) -> egui::InnerResponse<Option<R>> {
+        combo_box::show_combo_box(self, combo_box, cancel_action, add_contents)
+    }
+
+    fn kbgp_tab_bar<T: Clone + PartialEq>(
+        &mut self,
+        selected: &mut T,
+        tabs: impl IntoIterator<Item = (T, impl Into<egui::WidgetText>)>,
+    ) -> egui::Response {
+        tab_bar::show_tab_bar(self, selected, tabs)
+    }
 }
 
 impl KbgpEguiUiCtxExt for egui::Ui {
@@ -1073,6 +2000,18 @@ impl KbgpEguiUiCtxExt for egui::Ui {
         self.ctx().kbgp_set_focus_label(label);
     }
 
+    fn kbgp_remember_focus(&self, menu: impl std::hash::Hash) {
+        self.ctx().kbgp_remember_focus(menu);
+    }
+
+    fn kbgp_push_menu<K: 'static + Clone + std::hash::Hash + Send + Sync>(&self, menu: K) {
+        self.ctx().kbgp_push_menu(menu);
+    }
+
+    fn kbgp_back<K: 'static + Clone>(&self) -> Option<K> {
+        self.ctx().kbgp_back()
+    }
+
     fn kbgp_user_action<T: 'static + Clone>(&self) -> Option<T> {
         self.ctx().kbgp_user_action()
     }
@@ -1080,6 +2019,26 @@ impl KbgpEguiUiCtxExt for egui::Ui {
     fn kbgp_user_action_released<T: 'static + Clone>(&self) -> Option<T> {
         self.ctx().kbgp_user_action_released()
     }
+
+    fn kbgp_set_input_formatter(&self, formatter: impl KbgpInputFormatter) {
+        self.ctx().kbgp_set_input_formatter(formatter);
+    }
+
+    fn kbgp_input_formatter(&self) -> std::sync::Arc<dyn KbgpInputFormatter> {
+        self.ctx().kbgp_input_formatter()
+    }
+
+    fn kbgp_set_glyph_atlas(&self, atlas: KbgpGlyphAtlas) {
+        self.ctx().kbgp_set_glyph_atlas(atlas);
+    }
+
+    fn kbgp_set_controller_family(&self, gamepad: Entity, family: KbgpControllerFamily) {
+        self.ctx().kbgp_set_controller_family(gamepad, family);
+    }
+
+    fn kbgp_set_pending_input_prompt(&self, prompt: KbgpPendingInputPrompt) {
+        self.ctx().kbgp_set_pending_input_prompt(prompt);
+    }
 }
 
 impl KbgpEguiUiCtxExt for egui::Context {
@@ -1129,6 +2088,62 @@ impl KbgpEguiUiCtxExt for egui::Context {
         }
     }
 
+    fn kbgp_remember_focus(&self, menu: impl std::hash::Hash) {
+        let menu = egui::Id::new(menu);
+        let focused = self.memory(|memory| memory.focused());
+        let kbgp = kbgp_get(self);
+        let mut kbgp = kbgp.lock();
+        let Kbgp { common, state, .. } = &mut *kbgp;
+        let entered = common.current_menu != Some(menu);
+        common.current_menu = Some(menu);
+        common.current_menu_seen_this_frame = true;
+        let KbgpState::Navigation(state) = state else {
+            return;
+        };
+        if !entered {
+            if let Some(focused) = focused.filter(|focused| common.nodes.contains_key(focused)) {
+                common.menus_last_focus.insert(menu, focused);
+            }
+        } else if state
+            .focus_label
+            .as_ref()
+            .is_none_or(|label| label.is::<KbgpInitialFocusLabel>())
+        {
+            state.restore_focus = common.menus_last_focus.get(&menu).copied();
+            // In case the remembered widget no longer exists.
+            state.focus_label = Some(Box::new(KbgpInitialFocusLabel));
+        }
+    }
+
+    fn kbgp_push_menu<K: 'static + Clone + std::hash::Hash + Send + Sync>(&self, menu: K) {
+        let id = egui::Id::new(&menu);
+        {
+            let kbgp = kbgp_get(self);
+            let mut kbgp = kbgp.lock();
+            let Kbgp { common, state, .. } = &mut *kbgp;
+            // The menu that was just popped is still drawn in the frame Back was pressed.
+            let going_back =
+                matches!(state, KbgpState::Navigation(state) if state.back_to.is_some());
+            if let Some(index) = common.menu_stack.iter().position(|(menu, _)| *menu == id) {
+                common.menu_stack.truncate(index + 1);
+            } else if !going_back {
+                common
+                    .menu_stack
+                    .push((id, std::sync::Arc::new(menu.clone())));
+            }
+        }
+        self.kbgp_remember_focus(menu);
+    }
+
+    fn kbgp_back<K: 'static + Clone>(&self) -> Option<K> {
+        let kbgp = kbgp_get(self);
+        let kbgp = kbgp.lock();
+        match &kbgp.state {
+            KbgpState::PendingInput(_) => None,
+            KbgpState::Navigation(state) => state.back_to.as_ref()?.downcast_ref().cloned(),
+        }
+    }
+
     fn kbgp_user_action<T: 'static + Clone>(&self) -> Option<T> {
         let kbgp = kbgp_get(self);
         let kbgp = kbgp.lock();
@@ -1157,4 +2172,34 @@ impl KbgpEguiUiCtxExt for egui::Context {
             },
         }
     }
+
+    fn kbgp_set_input_formatter(&self, formatter: impl KbgpInputFormatter) {
+        let kbgp = kbgp_get(self);
+        let mut kbgp = kbgp.lock();
+        kbgp.common.input_formatter = std::sync::Arc::new(formatter);
+    }
+
+    fn kbgp_input_formatter(&self) -> std::sync::Arc<dyn KbgpInputFormatter> {
+        let kbgp = kbgp_get(self);
+        let kbgp = kbgp.lock();
+        kbgp.common.input_formatter.clone()
+    }
+
+    fn kbgp_set_glyph_atlas(&self, atlas: KbgpGlyphAtlas) {
+        let kbgp = kbgp_get(self);
+        let mut kbgp = kbgp.lock();
+        kbgp.common.glyph_atlas = Some(std::sync::Arc::new(atlas));
+    }
+
+    fn kbgp_set_controller_family(&self, gamepad: Entity, family: KbgpControllerFamily) {
+        let kbgp = kbgp_get(self);
+        let mut kbgp = kbgp.lock();
+        kbgp.common.controller_families.insert(gamepad, family);
+    }
+
+    fn kbgp_set_pending_input_prompt(&self, prompt: KbgpPendingInputPrompt) {
+        let kbgp = kbgp_get(self);
+        let mut kbgp = kbgp.lock();
+        kbgp.common.pending_input_prompt = prompt;
+    }
 }
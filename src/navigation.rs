@@ -1,39 +1,55 @@
 use std::any::Any;
 
 use crate::egui;
-use bevy::platform::collections::HashMap;
+use bevy::platform::collections::{HashMap, HashSet};
 use bevy::prelude::*;
 
-use crate::KbgpCommon;
+use crate::scrolling::{scroll_focused_scroll_area, KbgpScrollBinding};
+use crate::virtual_cursor::KbgpVirtualCursorBinding;
+use crate::virtual_keyboard::VirtualKeyboardState;
+use crate::{KbgpCommon, NodeData};
 
-const INPUT_MASK_UP: u8 = 1;
-const INPUT_MASK_DOWN: u8 = 2;
-const INPUT_MASK_VERTICAL: u8 = INPUT_MASK_UP | INPUT_MASK_DOWN;
-const INPUT_MASK_LEFT: u8 = 4;
-const INPUT_MASK_RIGHT: u8 = 8;
-const INPUT_MASK_HORIZONTAL: u8 = INPUT_MASK_LEFT | INPUT_MASK_RIGHT;
+const INPUT_MASK_UP: u16 = 1;
+const INPUT_MASK_DOWN: u16 = 2;
+const INPUT_MASK_VERTICAL: u16 = INPUT_MASK_UP | INPUT_MASK_DOWN;
+const INPUT_MASK_LEFT: u16 = 4;
+const INPUT_MASK_RIGHT: u16 = 8;
+const INPUT_MASK_HORIZONTAL: u16 = INPUT_MASK_LEFT | INPUT_MASK_RIGHT;
 
-const INPUT_MASK_CLICK: u8 = 16;
-const INPUT_MASK_USER_ACTION: u8 = 32;
+const INPUT_MASK_CLICK: u16 = 16;
+const INPUT_MASK_USER_ACTION: u16 = 32;
 
+const INPUT_MASK_PREV_TAB: u16 = 64;
+const INPUT_MASK_NEXT_TAB: u16 = 128;
+const INPUT_MASK_TABS: u16 = INPUT_MASK_PREV_TAB | INPUT_MASK_NEXT_TAB;
+
+const INPUT_MASK_TOGGLE_VIRTUAL_CURSOR: u16 = 256;
+const INPUT_MASK_BACK: u16 = 512;
+
+/// While adjusting a value, the step grows by one step every this many repeats.
+const ADJUSTMENT_ACCELERATION_REPEATS: u32 = 10;
+const MAX_ADJUSTMENT_MULTIPLIER: u32 = 10;
+
+/// Tracking of the activation button, for the `_released` methods. `I` identifies the focused
+/// node and `A` holds the user action.
 #[derive(Default)]
-pub(crate) enum PendingReleaseState {
+pub(crate) enum PendingReleaseState<I = egui::Id, A = Box<dyn Any + Send + Sync>> {
     #[default]
     Idle,
     NodeHeld {
-        id: egui::Id,
+        id: I,
         is_user_action: bool,
-        user_action: Option<Box<dyn Any + Send + Sync>>,
+        user_action: Option<A>,
     },
     NodeHoldReleased {
-        id: egui::Id,
-        user_action: Option<Box<dyn Any + Send + Sync>>,
+        id: I,
+        user_action: Option<A>,
     },
     GloballyHeld {
-        user_action: Option<Box<dyn Any + Send + Sync>>,
+        user_action: Option<A>,
     },
     GlobalHoldReleased {
-        user_action: Box<dyn Any + Send + Sync>,
+        user_action: A,
     },
     Invalidated {
         // `kbgp_clear_input` sets this to `true`, so that the invalidation could survive the
@@ -42,19 +58,197 @@ pub(crate) enum PendingReleaseState {
     },
 }
 
+impl<I: Copy + PartialEq, A> PendingReleaseState<I, A> {
+    /// Track the click and user action input of a frame.
+    pub(crate) fn update(
+        &mut self,
+        input: u16,
+        current_focus: Option<I>,
+        prev_user_action: Option<A>,
+    ) {
+        match self {
+            PendingReleaseState::Idle => {
+                *self = match input & (INPUT_MASK_CLICK | INPUT_MASK_USER_ACTION) {
+                    0 => PendingReleaseState::Idle,
+                    INPUT_MASK_CLICK => {
+                        if let Some(current_focus) = current_focus {
+                            PendingReleaseState::NodeHeld {
+                                id: current_focus,
+                                is_user_action: false,
+                                user_action: prev_user_action,
+                            }
+                        } else {
+                            PendingReleaseState::Invalidated {
+                                cooldown_frame: false,
+                            }
+                        }
+                    }
+                    INPUT_MASK_USER_ACTION => {
+                        if let Some(current_focus) = current_focus {
+                            PendingReleaseState::NodeHeld {
+                                id: current_focus,
+                                is_user_action: true,
+                                user_action: prev_user_action,
+                            }
+                        } else {
+                            PendingReleaseState::GloballyHeld {
+                                user_action: prev_user_action,
+                            }
+                        }
+                    }
+                    _ => PendingReleaseState::Invalidated {
+                        cooldown_frame: false,
+                    },
+                }
+            }
+            PendingReleaseState::NodeHeld {
+                id,
+                is_user_action,
+                user_action,
+            } => match input & (INPUT_MASK_CLICK | INPUT_MASK_USER_ACTION) {
+                0 => {
+                    if current_focus == Some(*id) {
+                        *self = PendingReleaseState::NodeHoldReleased {
+                            id: *id,
+                            user_action: user_action.take().or(prev_user_action),
+                        };
+                    } else {
+                        *self = PendingReleaseState::Idle;
+                    }
+                }
+                INPUT_MASK_CLICK => {
+                    if *is_user_action {
+                        *self = PendingReleaseState::Invalidated {
+                            cooldown_frame: false,
+                        };
+                    }
+                }
+                INPUT_MASK_USER_ACTION => {
+                    if !*is_user_action {
+                        *self = PendingReleaseState::Invalidated {
+                            cooldown_frame: false,
+                        };
+                    } else if prev_user_action.is_some() {
+                        *user_action = prev_user_action;
+                    }
+                }
+                _ => {
+                    *self = PendingReleaseState::Invalidated {
+                        cooldown_frame: false,
+                    };
+                }
+            },
+            PendingReleaseState::NodeHoldReleased {
+                id: _,
+                user_action: _,
+            } => {
+                // The key/button may already be pressed again.
+                *self = PendingReleaseState::Idle;
+                self.update(input, current_focus, prev_user_action);
+            }
+            PendingReleaseState::GloballyHeld { user_action } => {
+                match input & (INPUT_MASK_CLICK | INPUT_MASK_USER_ACTION) {
+                    0 => {
+                        if let Some(user_action) = user_action.take().or(prev_user_action) {
+                            *self = PendingReleaseState::GlobalHoldReleased { user_action };
+                        } else {
+                            *self = PendingReleaseState::Idle;
+                        }
+                    }
+                    INPUT_MASK_USER_ACTION => {
+                        if prev_user_action.is_some() {
+                            *user_action = prev_user_action;
+                        }
+                    }
+                    _ => {
+                        *self = PendingReleaseState::Invalidated {
+                            cooldown_frame: false,
+                        };
+                    }
+                }
+            }
+            PendingReleaseState::GlobalHoldReleased { user_action: _ } => {
+                *self = PendingReleaseState::Idle;
+                self.update(input, current_focus, prev_user_action);
+            }
+            PendingReleaseState::Invalidated { cooldown_frame } => {
+                if *cooldown_frame {
+                    *cooldown_frame = false;
+                } else if input & INPUT_MASK_CLICK == 0 {
+                    *self = PendingReleaseState::Idle;
+                }
+            }
+        }
+    }
+}
+
 #[derive(Default)]
 pub(crate) struct KbgpNavigationState {
-    pub(crate) prev_input: u8,
+    pub(crate) input_repeat: InputRepeat,
     pub(crate) pending_release_state: PendingReleaseState,
-    pub(crate) next_navigation: f64,
+    pub(crate) adjustment: Option<f64>,
+    pub(crate) tab_step: isize,
+    /// The menu to go back to, when Back popped the menu stack this frame.
+    pub(crate) back_to: Option<std::sync::Arc<dyn Any + Send + Sync>>,
+    pub(crate) virtual_keyboard: Option<VirtualKeyboardState>,
+    pub(crate) prev_text_edit_input: u16,
+    pub(crate) text_edit_escape_candidate: Option<TextEditEscapeCandidate>,
     pub(crate) user_action: Option<Box<dyn Any + Send + Sync>>,
     pub(crate) focus_label: Option<Box<dyn Any + Send + Sync>>,
     pub(crate) next_frame_focus_label: Option<Box<dyn Any + Send + Sync>>,
     pub(crate) focus_on: Option<egui::Id>,
+    /// The remembered focus of a menu that was just shown. See `kbgp_remember_focus`.
+    pub(crate) restore_focus: Option<egui::Id>,
+    /// Set when the focus was restored, so that the initial focus will not override it.
+    pub(crate) focus_restored: bool,
     pub(crate) last_focus: Option<egui::Id>,
     pub(crate) mouse_was_last_on: Option<egui::Id>,
 }
 
+/// Timing of held keys/buttons - the first press applies immediately, and holding them repeats it
+/// after a delay.
+#[derive(Default)]
+pub(crate) struct InputRepeat {
+    pub(crate) prev_input: u16,
+    next_navigation: f64,
+    pub(crate) repeats: u32,
+}
+
+impl InputRepeat {
+    /// The part of the held input that should be applied in this frame.
+    pub(crate) fn apply(
+        &mut self,
+        input: u16,
+        current_time: f64,
+        secs_after_first_input: f64,
+        secs_between_inputs: f64,
+    ) -> u16 {
+        let mut effective_input = input;
+        if input != 0 {
+            if self.prev_input != input {
+                effective_input &= !self.prev_input;
+                self.next_navigation = current_time + secs_after_first_input;
+                self.repeats = 0;
+            } else if current_time < self.next_navigation {
+                effective_input = 0;
+            } else {
+                self.next_navigation = current_time + secs_between_inputs;
+                self.repeats += 1;
+            }
+        }
+        self.prev_input = input;
+        effective_input
+    }
+}
+
+/// Directions the keyboard tried to move the caret of a `TextEdit` in. If the caret did not move,
+/// it is at the edge of the text and the next frame navigates away from the `TextEdit`.
+pub(crate) struct TextEditEscapeCandidate {
+    id: egui::Id,
+    caret: Option<egui::text::CCursorRange>,
+    input: u16,
+}
+
 /// An option of [`KbgpPrepare`](crate::KbgpPrepare).
 pub struct KbgpPrepareNavigation {
     /// When the player holds a key/button, KBGP will wait `secs_after_first_input` seconds before
@@ -68,11 +262,56 @@ pub struct KbgpPrepareNavigation {
     ///
     /// Default: 0.04 seconds.
     pub secs_between_inputs: f64,
-    input: u8,
+    /// How to treat disabled widgets (e.g. widgets added with `ui.add_enabled(false, ...)`).
+    ///
+    /// Can be overridden per widget with
+    /// [`kbgp_when_disabled`](crate::KbgpEguiResponseExt::kbgp_when_disabled).
+    ///
+    /// Default: [`KbgpDisabledWidgetPolicy::Focusable`].
+    pub disabled_widgets: KbgpDisabledWidgetPolicy,
+    /// Whether or not to navigate between all the interactive egui widgets, not just the ones
+    /// marked with [`kbgp_navigation`](crate::KbgpEguiResponseExt::kbgp_navigation).
+    ///
+    /// Widgets can opt out with
+    /// [`kbgp_exclude_navigation`](crate::KbgpEguiResponseExt::kbgp_exclude_navigation).
+    /// Disabled widgets that were not marked with `kbgp_navigation` are always skipped.
+    ///
+    /// Default: `false`.
+    pub auto_navigation: bool,
+    /// Which keyboard navigation still works while a `TextEdit` has the focus.
+    ///
+    /// Only affects [`navigate_keyboard_by_binding`](Self::navigate_keyboard_by_binding) - other
+    /// input is not suspended while editing text.
+    pub text_edit_escape: KbgpTextEditEscape,
+    input: u16,
+    text_edit_input: u16,
+    editing_text: bool,
+    pub(crate) scroll_velocity: egui::Vec2,
+    virtual_cursor_active: bool,
+    virtual_cursor_velocity: egui::Vec2,
     user_action: Option<Box<dyn Any + Send + Sync>>,
 }
 
 impl KbgpPrepareNavigation {
+    /// A handle with the default options, for navigation that does not involve egui.
+    #[cfg(feature = "bevy_ui")]
+    pub(crate) fn for_nodes() -> Self {
+        Self {
+            secs_after_first_input: 0.6,
+            secs_between_inputs: 0.04,
+            disabled_widgets: Default::default(),
+            auto_navigation: false,
+            text_edit_escape: Default::default(),
+            input: 0,
+            text_edit_input: 0,
+            editing_text: false,
+            scroll_velocity: egui::Vec2::ZERO,
+            virtual_cursor_active: false,
+            virtual_cursor_velocity: egui::Vec2::ZERO,
+            user_action: None,
+        }
+    }
+
     pub fn apply_action(&mut self, command: &KbgpNavCommand) {
         match command {
             KbgpNavCommand::NavigateUp => {
@@ -90,6 +329,18 @@ impl KbgpPrepareNavigation {
             KbgpNavCommand::Click => {
                 self.input |= INPUT_MASK_CLICK;
             }
+            KbgpNavCommand::PrevTab => {
+                self.input |= INPUT_MASK_PREV_TAB;
+            }
+            KbgpNavCommand::NextTab => {
+                self.input |= INPUT_MASK_NEXT_TAB;
+            }
+            KbgpNavCommand::ToggleVirtualCursor => {
+                self.input |= INPUT_MASK_TOGGLE_VIRTUAL_CURSOR;
+            }
+            KbgpNavCommand::Back => {
+                self.input |= INPUT_MASK_BACK;
+            }
             KbgpNavCommand::User(action) => {
                 self.user_action = Some(action());
                 self.input |= INPUT_MASK_USER_ACTION;
@@ -97,6 +348,55 @@ impl KbgpPrepareNavigation {
         }
     }
 
+    /// Whether or not a `TextEdit` has the focus.
+    ///
+    /// While editing text, [`navigate_keyboard_by_binding`](Self::navigate_keyboard_by_binding)
+    /// leaves the arrow keys (and other directional bindings) to the caret. See
+    /// [`text_edit_escape`](Self::text_edit_escape).
+    pub fn editing_text(&self) -> bool {
+        self.editing_text
+    }
+
+    /// Whether or not the virtual cursor is active.
+    ///
+    /// See [`KbgpVirtualCursorBinding`] for when it is active. While it is active,
+    /// [`navigate_gamepad_by_binding`](Self::navigate_gamepad_by_binding) does not navigate with
+    /// the left stick.
+    pub fn virtual_cursor_active(&self) -> bool {
+        self.virtual_cursor_active
+    }
+
+    /// Apply a command, unless a `TextEdit` has the focus and the command is one that should
+    /// move the caret (or type a letter) instead.
+    fn apply_action_unless_typing(&mut self, action: &KbgpNavCommand) {
+        if self.editing_text {
+            match action {
+                KbgpNavCommand::NavigateUp => {
+                    self.text_edit_input |= INPUT_MASK_UP;
+                    return;
+                }
+                KbgpNavCommand::NavigateDown => {
+                    self.text_edit_input |= INPUT_MASK_DOWN;
+                    return;
+                }
+                KbgpNavCommand::NavigateLeft => {
+                    self.text_edit_input |= INPUT_MASK_LEFT;
+                    return;
+                }
+                KbgpNavCommand::NavigateRight => {
+                    self.text_edit_input |= INPUT_MASK_RIGHT;
+                    return;
+                }
+                // Tab switching is usually bound to letters, which should be typed.
+                KbgpNavCommand::PrevTab | KbgpNavCommand::NextTab => {
+                    return;
+                }
+                _ => {}
+            }
+        }
+        self.apply_action(action);
+    }
+
     /// Navigate the UI with the keyboard.
     ///
     /// Note: Unless
@@ -110,7 +410,11 @@ impl KbgpPrepareNavigation {
     ) {
         for key in keys.get_pressed() {
             if let Some(action) = binding.get(key) {
-                self.apply_action(action);
+                if self.text_edit_escape.keys.contains(key) {
+                    self.apply_action(action);
+                } else {
+                    self.apply_action_unless_typing(action);
+                }
             }
         }
         if mimic_default_activation
@@ -122,31 +426,79 @@ impl KbgpPrepareNavigation {
         }
     }
 
+    /// Scroll with the gamepad's axes, according to the binding.
+    ///
+    /// See [`KbgpScrollBinding`] for which `ScrollArea` gets scrolled.
+    pub fn scroll_gamepad_by_binding(&mut self, gamepad: &Gamepad, binding: &KbgpScrollBinding) {
+        self.scroll_velocity += binding.velocity(gamepad);
+    }
+
+    /// Move the virtual cursor with the gamepad's axes, according to the binding.
+    ///
+    /// Only has effect while the virtual cursor is active - see [`KbgpVirtualCursorBinding`].
+    pub fn move_virtual_cursor_by_binding(
+        &mut self,
+        gamepad: &Gamepad,
+        binding: &KbgpVirtualCursorBinding,
+    ) {
+        self.virtual_cursor_velocity += binding.velocity(gamepad);
+    }
+
+    /// The non-user commands applied so far this frame.
+    #[cfg(feature = "recording")]
+    pub(crate) fn applied_commands(&self) -> impl Iterator<Item = KbgpNavCommand> + '_ {
+        [
+            (INPUT_MASK_UP, KbgpNavCommand::NavigateUp),
+            (INPUT_MASK_DOWN, KbgpNavCommand::NavigateDown),
+            (INPUT_MASK_LEFT, KbgpNavCommand::NavigateLeft),
+            (INPUT_MASK_RIGHT, KbgpNavCommand::NavigateRight),
+            (INPUT_MASK_CLICK, KbgpNavCommand::Click),
+            (INPUT_MASK_PREV_TAB, KbgpNavCommand::PrevTab),
+            (INPUT_MASK_NEXT_TAB, KbgpNavCommand::NextTab),
+            (
+                INPUT_MASK_TOGGLE_VIRTUAL_CURSOR,
+                KbgpNavCommand::ToggleVirtualCursor,
+            ),
+            (INPUT_MASK_BACK, KbgpNavCommand::Back),
+        ]
+        .into_iter()
+        .filter_map(|(mask, command)| (self.input & mask != 0).then_some(command))
+    }
+
+    /// The user action applied so far this frame.
+    #[cfg(feature = "recording")]
+    pub(crate) fn applied_user_action(&self) -> Option<&(dyn Any + Send + Sync)> {
+        self.user_action.as_deref()
+    }
+
     /// Navigate the UI with a gamepad.
     ///
     /// * Use both left stick and d-pad for navigation.
+    /// * The left stick does not navigate while the virtual cursor is active.
     pub fn navigate_gamepad_by_binding(
         &mut self,
         gamepad: &Gamepad,
         binding: &HashMap<GamepadButton, KbgpNavCommand>,
     ) {
-        for (axis_type, action_for_negative, action_for_positive) in [
-            (
-                GamepadAxis::LeftStickX,
-                KbgpNavCommand::NavigateLeft,
-                KbgpNavCommand::NavigateRight,
-            ),
-            (
-                GamepadAxis::LeftStickY,
-                KbgpNavCommand::NavigateDown,
-                KbgpNavCommand::NavigateUp,
-            ),
-        ] {
-            if let Some(axis_value) = gamepad.get(axis_type) {
-                if axis_value < -0.5 {
-                    self.apply_action(&action_for_negative)
-                } else if 0.5 < axis_value {
-                    self.apply_action(&action_for_positive)
+        if !self.virtual_cursor_active {
+            for (axis_type, action_for_negative, action_for_positive) in [
+                (
+                    GamepadAxis::LeftStickX,
+                    KbgpNavCommand::NavigateLeft,
+                    KbgpNavCommand::NavigateRight,
+                ),
+                (
+                    GamepadAxis::LeftStickY,
+                    KbgpNavCommand::NavigateDown,
+                    KbgpNavCommand::NavigateUp,
+                ),
+            ] {
+                if let Some(axis_value) = gamepad.get(axis_type) {
+                    if axis_value < -0.5 {
+                        self.apply_action(&action_for_negative)
+                    } else if 0.5 < axis_value {
+                        self.apply_action(&action_for_positive)
+                    }
                 }
             }
         }
@@ -156,34 +508,147 @@ impl KbgpPrepareNavigation {
             }
         }
     }
+
+    /// Navigate the UI with actions from an input mapping crate.
+    ///
+    /// Applies the command bound to each action that `is_pressed` reports as pressed. For
+    /// leafwing-input-manager's `ActionState`, the `leafwing` feature adds
+    /// `navigate_by_action_state`.
+    ///
+    /// Like with [`navigate_keyboard_by_binding`](Self::navigate_keyboard_by_binding), while a
+    /// `TextEdit` has the focus the directional actions move the caret and the tab actions are
+    /// ignored - see [`KbgpTextEditEscape`] for when they navigate away from it. The escape keys
+    /// do not apply to actions.
+    pub fn navigate_by_actions<A: Eq + std::hash::Hash>(
+        &mut self,
+        binding: &HashMap<A, KbgpNavCommand>,
+        is_pressed: impl Fn(&A) -> bool,
+    ) {
+        for (action, command) in binding.iter() {
+            if is_pressed(action) {
+                self.apply_action_unless_typing(command);
+            }
+        }
+    }
 }
 
 impl KbgpNavigationState {
     pub(crate) fn prepare(
         &mut self,
-        common: &KbgpCommon,
+        common: &mut KbgpCommon,
         egui_ctx: &egui::Context,
+        current_time: f64,
         prepare_dlg: impl FnOnce(&mut KbgpPrepareNavigation),
     ) {
+        let focused = egui_ctx.memory(|memory| memory.focused());
+        let text_edit_state =
+            focused.and_then(|focused| egui::text_edit::TextEditState::load(egui_ctx, focused));
+        let virtual_cursor_scope = focused
+            .and_then(|focused| common.nodes.get(&focused))
+            .filter(|node_data| node_data.virtual_cursor)
+            .map(|node_data| node_data.rect);
         let mut handle = KbgpPrepareNavigation {
             secs_after_first_input: 0.6,
             secs_between_inputs: 0.04,
+            disabled_widgets: common.disabled_widgets,
+            auto_navigation: common.auto_navigation,
+            text_edit_escape: std::mem::take(&mut common.text_edit_escape),
             input: 0,
+            text_edit_input: 0,
+            editing_text: text_edit_state.is_some(),
+            scroll_velocity: egui::Vec2::ZERO,
+            virtual_cursor_active: common.virtual_cursor.toggled_on
+                || virtual_cursor_scope.is_some(),
+            virtual_cursor_velocity: egui::Vec2::ZERO,
             user_action: None,
         };
 
         prepare_dlg(&mut handle);
+        common.disabled_widgets = handle.disabled_widgets;
+        common.auto_navigation = handle.auto_navigation;
+        common.text_edit_escape = std::mem::take(&mut handle.text_edit_escape);
+        self.apply_text_edit_escape(&mut handle, focused, text_edit_state);
+        if handle.auto_navigation {
+            register_interactive_widgets(common, egui_ctx);
+        }
         let prev_user_action = self.user_action.take();
+        self.adjustment = None;
+        self.tab_step = 0;
+        self.back_to = None;
+        self.apply_scroll(egui_ctx, &handle, focused);
+        self.apply_virtual_cursor(common, egui_ctx, &mut handle, focused, virtual_cursor_scope);
+        if let Some(keyboard) = &mut self.virtual_keyboard {
+            if Some(keyboard.target) != focused || !keyboard.press_pending(egui_ctx) {
+                self.virtual_keyboard = None;
+            }
+        }
+        let effective_input = self.input_repeat.apply(
+            handle.input,
+            current_time,
+            handle.secs_after_first_input,
+            handle.secs_between_inputs,
+        );
         if handle.input != 0 {
-            let mut effective_input = handle.input;
-            let current_time = egui_ctx.input(|input| input.time);
-            if self.prev_input != handle.input {
-                effective_input &= !self.prev_input;
-                self.next_navigation = current_time + handle.secs_after_first_input;
-            } else if current_time < self.next_navigation {
-                effective_input = 0;
-            } else {
-                self.next_navigation = current_time + handle.secs_between_inputs;
+            let mut effective_input = effective_input;
+            if 0 < self.input_repeat.repeats {
+                // Holding Back should not pop the whole menu stack.
+                effective_input &= !INPUT_MASK_BACK;
+            }
+
+            let focused_node = focused.and_then(|focused| common.nodes.get(&focused));
+            let focused_is_adjustable = focused_node.is_some_and(|node_data| node_data.adjustable);
+            if focused_is_adjustable {
+                let direction = match effective_input & INPUT_MASK_HORIZONTAL {
+                    INPUT_MASK_LEFT => -1.0,
+                    INPUT_MASK_RIGHT => 1.0,
+                    _ => 0.0,
+                };
+                if direction != 0.0 {
+                    let multiplier = (1 + self.input_repeat.repeats
+                        / ADJUSTMENT_ACCELERATION_REPEATS)
+                        .min(MAX_ADJUSTMENT_MULTIPLIER);
+                    self.adjustment = Some(direction * multiplier as f64);
+                }
+                if handle.input & INPUT_MASK_HORIZONTAL != 0 {
+                    // Sliders and drag values would also change the value with the arrow keys.
+                    egui_ctx.input_mut(|input| {
+                        input.events.retain(|event| {
+                            !matches!(
+                                event,
+                                egui::Event::Key {
+                                    key: egui::Key::ArrowLeft | egui::Key::ArrowRight,
+                                    ..
+                                }
+                            )
+                        });
+                    });
+                }
+                effective_input &= !INPUT_MASK_HORIZONTAL;
+            }
+
+            if let Some(keyboard) = &mut self.virtual_keyboard {
+                for (mask, direction) in [
+                    (INPUT_MASK_UP, KbgpNavDirection::Up),
+                    (INPUT_MASK_DOWN, KbgpNavDirection::Down),
+                    (INPUT_MASK_LEFT, KbgpNavDirection::Left),
+                    (INPUT_MASK_RIGHT, KbgpNavDirection::Right),
+                ] {
+                    if effective_input & mask != 0 {
+                        keyboard.move_cursor(direction);
+                    }
+                }
+                if effective_input & INPUT_MASK_CLICK != 0 && !keyboard.press_under_cursor(egui_ctx)
+                {
+                    self.virtual_keyboard = None;
+                }
+                effective_input &=
+                    !(INPUT_MASK_VERTICAL | INPUT_MASK_HORIZONTAL | INPUT_MASK_CLICK);
+            } else if effective_input & INPUT_MASK_CLICK != 0
+                && focused_node.is_some_and(|node_data| node_data.virtual_keyboard)
+            {
+                // Instead of sending Enter, which would make the `TextEdit` surrender the focus.
+                self.virtual_keyboard = focused.map(VirtualKeyboardState::new);
+                effective_input &= !INPUT_MASK_CLICK;
             }
 
             if effective_input & INPUT_MASK_CLICK != 0 {
@@ -202,36 +667,38 @@ impl KbgpNavigationState {
                 self.user_action = handle.user_action;
             }
 
+            self.tab_step = match effective_input & INPUT_MASK_TABS {
+                INPUT_MASK_PREV_TAB => -1,
+                INPUT_MASK_NEXT_TAB => 1,
+                _ => 0,
+            };
+
+            // Going back from the root menu is left for the game to handle with a user action.
+            if effective_input & INPUT_MASK_BACK != 0 && 1 < common.menu_stack.len() {
+                common.menu_stack.pop();
+                self.back_to = common.menu_stack.last().map(|(_, menu)| menu.clone());
+            }
+
             let mut move_focus_to = None;
 
             match effective_input & INPUT_MASK_VERTICAL {
                 INPUT_MASK_UP => {
-                    move_focus_to =
-                        self.move_focus(common, egui_ctx, None, |egui::Pos2 { x, y }| egui::Pos2 {
-                            x: -x,
-                            y: -y,
-                        });
+                    move_focus_to = self.move_focus(common, egui_ctx, None, KbgpNavDirection::Up);
                 }
                 INPUT_MASK_DOWN => {
-                    move_focus_to = self.move_focus(common, egui_ctx, None, |p| p);
+                    move_focus_to = self.move_focus(common, egui_ctx, None, KbgpNavDirection::Down);
                 }
                 _ => {}
             }
 
-            // Note: Doing transpose instead of rotation so that starting navigation without
-            // anything focused will make left similar to up and right similar to down.
             match effective_input & INPUT_MASK_HORIZONTAL {
                 INPUT_MASK_LEFT => {
                     move_focus_to =
-                        self.move_focus(common, egui_ctx, move_focus_to, |egui::Pos2 { x, y }| {
-                            egui::Pos2 { x: -y, y: -x }
-                        });
+                        self.move_focus(common, egui_ctx, move_focus_to, KbgpNavDirection::Left);
                 }
                 INPUT_MASK_RIGHT => {
                     move_focus_to =
-                        self.move_focus(common, egui_ctx, move_focus_to, |egui::Pos2 { x, y }| {
-                            egui::Pos2 { x: y, y: x }
-                        });
+                        self.move_focus(common, egui_ctx, move_focus_to, KbgpNavDirection::Right);
                 }
                 _ => {}
             }
@@ -241,123 +708,90 @@ impl KbgpNavigationState {
             }
         }
 
-        self.prev_input = handle.input;
+        let current_focus = egui_ctx.memory(|memory| memory.focused());
+        self.pending_release_state
+            .update(handle.input, current_focus, prev_user_action);
+    }
 
-        match &mut self.pending_release_state {
-            PendingReleaseState::Idle => {
-                self.pending_release_state = match handle.input
-                    & (INPUT_MASK_CLICK | INPUT_MASK_USER_ACTION)
-                {
-                    0 => PendingReleaseState::Idle,
-                    INPUT_MASK_CLICK => {
-                        if let Some(current_focus) = egui_ctx.memory(|memory| memory.focused()) {
-                            PendingReleaseState::NodeHeld {
-                                id: current_focus,
-                                is_user_action: false,
-                                user_action: prev_user_action,
-                            }
-                        } else {
-                            PendingReleaseState::Invalidated {
-                                cooldown_frame: false,
-                            }
-                        }
-                    }
-                    INPUT_MASK_USER_ACTION => {
-                        if let Some(current_focus) = egui_ctx.memory(|memory| memory.focused()) {
-                            PendingReleaseState::NodeHeld {
-                                id: current_focus,
-                                is_user_action: true,
-                                user_action: prev_user_action,
-                            }
-                        } else {
-                            PendingReleaseState::GloballyHeld {
-                                user_action: prev_user_action,
-                            }
-                        }
-                    }
-                    _ => PendingReleaseState::Invalidated {
-                        cooldown_frame: false,
-                    },
-                }
+    fn apply_scroll(
+        &mut self,
+        egui_ctx: &egui::Context,
+        handle: &KbgpPrepareNavigation,
+        focused: Option<egui::Id>,
+    ) {
+        if handle.scroll_velocity == egui::Vec2::ZERO {
+            return;
+        }
+        let delta = handle.scroll_velocity * egui_ctx.input(|input| input.stable_dt);
+        if egui_ctx.input(|input| input.pointer.hover_pos().is_some()) {
+            // Scroll like the mouse wheel does - egui will pick the hovered `ScrollArea`.
+            egui_ctx.input_mut(|input| input.smooth_scroll_delta += delta);
+        } else {
+            scroll_focused_scroll_area(egui_ctx, focused, delta);
+        }
+    }
+
+    fn apply_virtual_cursor(
+        &mut self,
+        common: &mut KbgpCommon,
+        egui_ctx: &egui::Context,
+        handle: &mut KbgpPrepareNavigation,
+        focused: Option<egui::Id>,
+        scope: Option<egui::Rect>,
+    ) {
+        if handle.input & !self.input_repeat.prev_input & INPUT_MASK_TOGGLE_VIRTUAL_CURSOR != 0 {
+            common.virtual_cursor.toggled_on = !common.virtual_cursor.toggled_on;
+        }
+        let bounds = if common.virtual_cursor.toggled_on {
+            Some(egui_ctx.screen_rect())
+        } else {
+            scope
+        };
+        let Some(bounds) = bounds else {
+            common.virtual_cursor.deactivate();
+            return;
+        };
+        let start = focused
+            .and_then(|focused| common.nodes.get(&focused))
+            .map(|node_data| node_data.rect.center());
+        let delta = handle.virtual_cursor_velocity * egui_ctx.input(|input| input.stable_dt);
+        let click_held = handle.input & INPUT_MASK_CLICK != 0;
+        common
+            .virtual_cursor
+            .update(bounds, start, delta, click_held);
+        // The click was sent to the pointer - it should not activate the focused widget.
+        handle.input &= !INPUT_MASK_CLICK;
+        common.virtual_cursor.paint(egui_ctx);
+    }
+
+    fn apply_text_edit_escape(
+        &mut self,
+        handle: &mut KbgpPrepareNavigation,
+        focused: Option<egui::Id>,
+        text_edit_state: Option<egui::text_edit::TextEditState>,
+    ) {
+        let caret = text_edit_state.and_then(|state| state.cursor.char_range());
+        if let Some(candidate) = self.text_edit_escape_candidate.take() {
+            if Some(candidate.id) == focused && candidate.caret == caret {
+                handle.input |= candidate.input;
             }
-            PendingReleaseState::NodeHeld {
+        }
+
+        let mut escape_mask = 0;
+        if handle.text_edit_escape.vertical_at_edges {
+            escape_mask |= INPUT_MASK_VERTICAL;
+        }
+        if handle.text_edit_escape.horizontal_at_edges {
+            escape_mask |= INPUT_MASK_HORIZONTAL;
+        }
+        let newly_pressed = handle.text_edit_input & !self.prev_text_edit_input & escape_mask;
+        self.prev_text_edit_input = handle.text_edit_input;
+        if let Some(id) = focused.filter(|_| newly_pressed != 0) {
+            self.text_edit_escape_candidate = Some(TextEditEscapeCandidate {
                 id,
-                is_user_action,
-                user_action,
-            } => {
-                let current_focus = egui_ctx.memory(|memory| memory.focused());
-                match handle.input & (INPUT_MASK_CLICK | INPUT_MASK_USER_ACTION) {
-                    0 => {
-                        if current_focus == Some(*id) {
-                            self.pending_release_state = PendingReleaseState::NodeHoldReleased {
-                                id: *id,
-                                user_action: user_action.take().or(prev_user_action),
-                            };
-                        } else {
-                            self.pending_release_state = PendingReleaseState::Idle;
-                        }
-                    }
-                    INPUT_MASK_CLICK => {
-                        if *is_user_action {
-                            self.pending_release_state = PendingReleaseState::Invalidated {
-                                cooldown_frame: false,
-                            };
-                        }
-                    }
-                    INPUT_MASK_USER_ACTION => {
-                        if !*is_user_action {
-                            self.pending_release_state = PendingReleaseState::Invalidated {
-                                cooldown_frame: false,
-                            };
-                        } else if prev_user_action.is_some() {
-                            *user_action = prev_user_action;
-                        }
-                    }
-                    _ => {
-                        self.pending_release_state = PendingReleaseState::Invalidated {
-                            cooldown_frame: false,
-                        };
-                    }
-                }
-            }
-            PendingReleaseState::NodeHoldReleased {
-                id: _,
-                user_action: _,
-            } => {
-                self.pending_release_state = PendingReleaseState::Idle;
-            }
-            PendingReleaseState::GloballyHeld { user_action } => {
-                match handle.input & (INPUT_MASK_CLICK | INPUT_MASK_USER_ACTION) {
-                    0 => {
-                        if let Some(user_action) = user_action.take().or(prev_user_action) {
-                            self.pending_release_state =
-                                PendingReleaseState::GlobalHoldReleased { user_action };
-                        } else {
-                            self.pending_release_state = PendingReleaseState::Idle;
-                        }
-                    }
-                    INPUT_MASK_USER_ACTION => {
-                        if prev_user_action.is_some() {
-                            *user_action = prev_user_action;
-                        }
-                    }
-                    _ => {
-                        self.pending_release_state = PendingReleaseState::Invalidated {
-                            cooldown_frame: false,
-                        };
-                    }
-                }
-            }
-            PendingReleaseState::GlobalHoldReleased { user_action: _ } => {
-                self.pending_release_state = PendingReleaseState::Idle;
-            }
-            PendingReleaseState::Invalidated { cooldown_frame } => {
-                if *cooldown_frame {
-                    *cooldown_frame = false;
-                } else if handle.input & INPUT_MASK_CLICK == 0 {
-                    self.pending_release_state = PendingReleaseState::Idle;
-                }
-            }
+                caret,
+                input: newly_pressed,
+            });
         }
     }
 
@@ -366,94 +800,405 @@ impl KbgpNavigationState {
         common: &KbgpCommon,
         egui_ctx: &egui::Context,
         move_from: Option<egui::Id>,
-        transform_pos_downward: impl Fn(egui::Pos2) -> egui::Pos2,
+        direction: KbgpNavDirection,
     ) -> Option<egui::Id> {
-        let transform_rect_downward = |rect: egui::Rect| -> egui::Rect {
-            let egui::Pos2 {
-                x: mut left,
-                y: mut top,
-            } = transform_pos_downward(rect.min);
-            let egui::Pos2 {
-                x: mut right,
-                y: mut bottom,
-            } = transform_pos_downward(rect.max);
-            if right < left {
-                std::mem::swap(&mut left, &mut right);
-            }
-            if bottom < top {
-                std::mem::swap(&mut top, &mut bottom);
-            }
-            egui::Rect {
-                min: egui::Pos2 { x: left, y: top },
-                max: egui::Pos2 {
-                    x: right,
-                    y: bottom,
-                },
+        let move_from = move_from.or_else(|| egui_ctx.memory(|memory| memory.focused()));
+        navigation_target(common, move_from, direction)
+    }
+}
+
+/// Navigation between nodes that are not egui widgets (e.g. bevy_ui nodes), identified by `K`.
+///
+/// Uses the same bindings, repeat timing and release tracking as the egui navigation, but only
+/// moves the focus and activates the focused node.
+#[cfg(feature = "bevy_ui")]
+pub(crate) struct KbgpNodeNavigation<K> {
+    input_repeat: InputRepeat,
+    pending_release_state: PendingReleaseState<K, std::sync::Arc<dyn Any + Send + Sync>>,
+}
+
+#[cfg(feature = "bevy_ui")]
+impl<K> Default for KbgpNodeNavigation<K> {
+    fn default() -> Self {
+        Self {
+            input_repeat: Default::default(),
+            pending_release_state: Default::default(),
+        }
+    }
+}
+
+/// What the player did to the nodes in a frame.
+#[cfg(feature = "bevy_ui")]
+pub(crate) enum KbgpNodeNavigationOutcome<K> {
+    MoveFocus(K),
+    Activate {
+        node: K,
+        released: bool,
+        user_action: Option<std::sync::Arc<dyn Any + Send + Sync>>,
+    },
+}
+
+#[cfg(feature = "bevy_ui")]
+impl<K: Copy + PartialEq> KbgpNodeNavigation<K> {
+    pub(crate) fn apply(
+        &mut self,
+        handle: KbgpPrepareNavigation,
+        current_time: f64,
+        focused: Option<K>,
+        nodes: &[(K, egui::Rect)],
+    ) -> Vec<KbgpNodeNavigationOutcome<K>> {
+        let mut outcomes = Vec::new();
+        let effective_input = self.input_repeat.apply(
+            handle.input,
+            current_time,
+            handle.secs_after_first_input,
+            handle.secs_between_inputs,
+        );
+        let user_action = handle.user_action.map(std::sync::Arc::from);
+
+        if let Some(focused) = focused {
+            if effective_input & INPUT_MASK_CLICK != 0 {
+                outcomes.push(KbgpNodeNavigationOutcome::Activate {
+                    node: focused,
+                    released: false,
+                    user_action: None,
+                });
             }
-        };
-        let transformed_nodes = common
-            .nodes
-            .iter()
-            .map(|(id, data)| (id, transform_rect_downward(data.rect)));
-        let focused_node_id = move_from.or_else(|| egui_ctx.memory(|memory| memory.focused()));
-        if let Some(focused_node_id) = focused_node_id {
-            let focused_node_rect = if let Some(data) = common.nodes.get(&focused_node_id) {
-                transform_rect_downward(data.rect)
-            } else {
-                return Some(focused_node_id);
-            };
+            if effective_input & INPUT_MASK_USER_ACTION != 0 {
+                outcomes.push(KbgpNodeNavigationOutcome::Activate {
+                    node: focused,
+                    released: false,
+                    user_action: user_action.clone(),
+                });
+            }
+        }
 
-            #[derive(Debug)]
-            struct InfoForComparison {
-                min_y: f32,
-                max_y: f32,
-                x_drift: f32,
+        let mut current_focus = focused;
+        for (mask, direction) in [
+            (INPUT_MASK_UP, KbgpNavDirection::Up),
+            (INPUT_MASK_DOWN, KbgpNavDirection::Down),
+            (INPUT_MASK_LEFT, KbgpNavDirection::Left),
+            (INPUT_MASK_RIGHT, KbgpNavDirection::Right),
+        ] {
+            if effective_input & mask == 0 {
+                continue;
             }
-            transformed_nodes
-                .filter_map(|(id, rect)| {
-                    if *id == focused_node_id {
-                        return None;
-                    }
-                    let min_y_diff = rect.min.y - focused_node_rect.max.y;
-                    if min_y_diff < 0.0 {
-                        return None;
-                    }
-                    Some((
-                        id,
-                        InfoForComparison {
-                            min_y: min_y_diff,
-                            max_y: rect.max.y - focused_node_rect.max.y,
-                            x_drift: {
-                                if focused_node_rect.max.x < rect.min.x {
-                                    rect.max.x - focused_node_rect.min.x
-                                } else if rect.max.x < focused_node_rect.min.x {
-                                    focused_node_rect.max.x - rect.min.x
-                                } else {
-                                    0.0
-                                }
-                            },
-                        },
-                    ))
-                })
-                .min_by(|(_, a), (_, b)| {
-                    if a.max_y < b.min_y && b.max_y < a.min_y {
-                        a.x_drift.partial_cmp(&b.x_drift).unwrap()
-                    } else {
-                        (a.min_y + a.x_drift)
-                            .partial_cmp(&(b.min_y + b.x_drift))
-                            .unwrap()
-                    }
-                })
-                .map(|(id, _)| *id)
-        } else {
-            transformed_nodes
-                .map(|(id, rect)| (id, (rect.min.y, rect.min.x)))
-                .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
-                .map(|(id, _)| *id)
+            // A focused entity that is not (or no longer) a node moves as if nothing was focused.
+            let move_from = current_focus
+                .and_then(|focused| nodes.iter().find(|(node, _)| *node == focused))
+                .copied();
+            if let Some(target) = closest_in_direction(nodes.iter().copied(), move_from, direction)
+            {
+                current_focus = Some(target);
+            }
+        }
+        if let Some(target) = current_focus.filter(|target| Some(*target) != focused) {
+            outcomes.push(KbgpNodeNavigationOutcome::MoveFocus(target));
+        }
+
+        self.pending_release_state
+            .update(handle.input, current_focus, user_action);
+        if let PendingReleaseState::NodeHoldReleased { id, user_action } =
+            &self.pending_release_state
+        {
+            outcomes.push(KbgpNodeNavigationOutcome::Activate {
+                node: *id,
+                released: true,
+                user_action: user_action.clone(),
+            });
+        }
+        outcomes
+    }
+}
+
+/// Add the interactive widgets egui saw in the previous pass to the navigation nodes.
+fn register_interactive_widgets(common: &mut KbgpCommon, egui_ctx: &egui::Context) {
+    let style = egui_ctx.style();
+    egui_ctx.viewport(|viewport| {
+        for (_, widgets) in viewport.prev_pass.widgets.layers() {
+            let scroll_area_parts = scroll_area_part_ids(&style, widgets);
+            for widget in widgets {
+                if !is_navigable_widget(widget)
+                    || scroll_area_parts.contains(&widget.id)
+                    || common.excluded_nodes.contains(&widget.id)
+                {
+                    continue;
+                }
+                common.nodes.entry(widget.id).or_insert_with(|| NodeData {
+                    // Focusing disabled widgets requires `kbgp_navigation`.
+                    disabled_policy: Some(KbgpDisabledWidgetPolicy::Skip),
+                    seen_this_frame: false,
+                    ..NodeData::new(widget.rect, widget.layer_id, widget.enabled)
+                });
+            }
+        }
+    });
+}
+
+/// Whether navigation can reach a widget it was not told about - e.g. buttons (which sense
+/// clicks) and sliders (which only sense drags).
+pub(crate) fn is_navigable_widget(widget: &egui::WidgetRect) -> bool {
+    widget.sense.is_focusable() && (widget.sense.senses_click() || widget.sense.senses_drag())
+}
+
+/// The IDs of the widgets egui registers for the parts of `ScrollArea`s (and of resizable
+/// panels and windows), which navigation should not land on.
+///
+/// egui does not expose which `ScrollArea` a widget belongs to, so they are identified by how egui
+/// registers them:
+/// * Scroll bars and resize handles are strips, no thicker than a scroll bar, that sense drags.
+/// * Dragging the content of a `ScrollArea` (or a window) is sensed by a widget that covers it, and
+///   is registered before the widgets drawn inside it.
+///
+/// `widgets` must be all the widgets of a layer, in the order they were registered.
+pub(crate) fn scroll_area_part_ids(
+    style: &egui::Style,
+    widgets: &[egui::WidgetRect],
+) -> HashSet<egui::Id> {
+    let strip_thickness = style
+        .spacing
+        .scroll
+        .bar_width
+        .max(2.0 * style.interaction.resize_grab_radius_side);
+    widgets
+        .iter()
+        .enumerate()
+        .filter(|(index, widget)| {
+            if !widget.sense.senses_drag() {
+                return false;
+            }
+            if widget.rect.width().min(widget.rect.height()) <= strip_thickness {
+                return true;
+            }
+            !widget.sense.senses_click()
+                && widgets[index + 1..]
+                    .iter()
+                    .any(|inner| 0.0 < inner.rect.area() && widget.rect.contains_rect(inner.rect))
+        })
+        .map(|(_, widget)| widget.id)
+        .collect()
+}
+
+/// How KBGP navigation treats disabled widgets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KbgpDisabledWidgetPolicy {
+    /// Disabled widgets can be focused, but KBGP will not activate them.
+    ///
+    /// Since egui does not focus disabled widgets, KBGP keeps the focus on them and paints the
+    /// focus outline itself.
+    #[default]
+    Focusable,
+    /// Navigation skips over disabled widgets.
+    Skip,
+}
+
+/// Which keyboard navigation still works while a `TextEdit` has the focus.
+///
+/// While a `TextEdit` has the focus, the directional keyboard bindings (e.g. the arrow keys) move
+/// the caret instead of the focus. Gamepad navigation is not affected.
+#[derive(Debug, Clone)]
+pub struct KbgpTextEditEscape {
+    /// Keys that keep their navigation bindings while editing text.
+    ///
+    /// Default: Tab.
+    pub keys: HashSet<KeyCode>,
+    /// Whether or not up and down navigate away from the `TextEdit` when the caret cannot move
+    /// further in that direction - e.g. pressing up when the caret is at the start of the text.
+    /// Since egui moves the caret to the start (or end) of the text when it is on the first (or
+    /// last) line, pressing up on the first line navigates away on the second press.
+    ///
+    /// Default: `true`.
+    pub vertical_at_edges: bool,
+    /// Whether or not left and right navigate away from the `TextEdit` when the caret is at the
+    /// start or end of the text.
+    ///
+    /// Default: `false`.
+    pub horizontal_at_edges: bool,
+}
+
+impl Default for KbgpTextEditEscape {
+    fn default() -> Self {
+        Self {
+            keys: [KeyCode::Tab].into_iter().collect(),
+            vertical_at_edges: true,
+            horizontal_at_edges: false,
+        }
+    }
+}
+
+/// A direction in which the focus can move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum KbgpNavDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl KbgpNavDirection {
+    pub(crate) const ALL: [Self; 4] = [Self::Up, Self::Down, Self::Left, Self::Right];
+
+    /// Transform positions so that this direction becomes downward.
+    fn transform_pos_downward(self, egui::Pos2 { x, y }: egui::Pos2) -> egui::Pos2 {
+        match self {
+            KbgpNavDirection::Up => egui::Pos2 { x: -x, y: -y },
+            KbgpNavDirection::Down => egui::Pos2 { x, y },
+            // Note: Doing transpose instead of rotation so that starting navigation without
+            // anything focused will make left similar to up and right similar to down.
+            KbgpNavDirection::Left => egui::Pos2 { x: -y, y: -x },
+            KbgpNavDirection::Right => egui::Pos2 { x: y, y: x },
         }
     }
 }
 
+/// The node the focus would move to from `move_from` (or from nothing) in a direction.
+pub(crate) fn navigation_target(
+    common: &KbgpCommon,
+    move_from: Option<egui::Id>,
+    direction: KbgpNavDirection,
+) -> Option<egui::Id> {
+    let move_from = match move_from {
+        Some(id) => match common.nodes.get(&id) {
+            Some(data) => Some((id, data.rect)),
+            None => return Some(id),
+        },
+        None => None,
+    };
+    let candidates = common
+        .nodes
+        .iter()
+        .filter(|(_, data)| is_navigable(common, data))
+        .map(|(id, data)| (*id, data.rect));
+    closest_in_direction(candidates, move_from, direction)
+}
+
+/// Whether navigation can move the focus to the node.
+pub(crate) fn is_navigable(common: &KbgpCommon, data: &NodeData) -> bool {
+    !data.is_skipped(common.disabled_widgets)
+        && common
+            .modal_layer
+            .is_none_or(|modal_layer| data.layer_id == modal_layer)
+}
+
+/// Whether the focus can stay on the node after the UI was drawn - it was drawn this frame, it is
+/// not skipped, and it is in the modal layer if one was drawn this frame.
+pub(crate) fn can_keep_focus(
+    common: &KbgpCommon,
+    egui_ctx: &egui::Context,
+    id: egui::Id,
+    data: &NodeData,
+) -> bool {
+    // Nodes added by auto navigation are not marked when drawn, but egui keeps track of them.
+    let drawn = data.seen_this_frame
+        || egui_ctx.viewport(|viewport| viewport.prev_pass.widgets.get(id).is_some());
+    drawn
+        && !data.is_skipped(common.disabled_widgets)
+        && common
+            .modal_layer
+            .filter(|_| common.modal_layer_seen_this_frame)
+            .is_none_or(|modal_layer| data.layer_id == modal_layer)
+}
+
+/// The node navigation would start from when nothing is focused, out of the nodes that can keep
+/// the focus after the UI was drawn.
+pub(crate) fn first_focusable_node(
+    common: &KbgpCommon,
+    egui_ctx: &egui::Context,
+) -> Option<egui::Id> {
+    let candidates = common
+        .nodes
+        .iter()
+        .filter(|(id, data)| can_keep_focus(common, egui_ctx, **id, data))
+        .map(|(id, data)| (*id, data.rect));
+    closest_in_direction(candidates, None, KbgpNavDirection::Down)
+}
+
+/// The candidate the focus would move to from `move_from` (or from nothing) in a direction.
+///
+/// Used for both egui widgets and other kinds of nodes - only the rects matter.
+pub(crate) fn closest_in_direction<K: Copy + PartialEq>(
+    candidates: impl Iterator<Item = (K, egui::Rect)>,
+    move_from: Option<(K, egui::Rect)>,
+    direction: KbgpNavDirection,
+) -> Option<K> {
+    let transform_pos_downward = |pos| direction.transform_pos_downward(pos);
+    let transform_rect_downward = |rect: egui::Rect| -> egui::Rect {
+        let egui::Pos2 {
+            x: mut left,
+            y: mut top,
+        } = transform_pos_downward(rect.min);
+        let egui::Pos2 {
+            x: mut right,
+            y: mut bottom,
+        } = transform_pos_downward(rect.max);
+        if right < left {
+            std::mem::swap(&mut left, &mut right);
+        }
+        if bottom < top {
+            std::mem::swap(&mut top, &mut bottom);
+        }
+        egui::Rect {
+            min: egui::Pos2 { x: left, y: top },
+            max: egui::Pos2 {
+                x: right,
+                y: bottom,
+            },
+        }
+    };
+    let transformed_nodes = candidates.map(|(id, rect)| (id, transform_rect_downward(rect)));
+    if let Some((focused_node_id, focused_node_rect)) = move_from {
+        let focused_node_rect = transform_rect_downward(focused_node_rect);
+
+        #[derive(Debug)]
+        struct InfoForComparison {
+            min_y: f32,
+            max_y: f32,
+            x_drift: f32,
+        }
+        transformed_nodes
+            .filter_map(|(id, rect)| {
+                if id == focused_node_id {
+                    return None;
+                }
+                let min_y_diff = rect.min.y - focused_node_rect.max.y;
+                if min_y_diff < 0.0 {
+                    return None;
+                }
+                Some((
+                    id,
+                    InfoForComparison {
+                        min_y: min_y_diff,
+                        max_y: rect.max.y - focused_node_rect.max.y,
+                        x_drift: {
+                            if focused_node_rect.max.x < rect.min.x {
+                                rect.max.x - focused_node_rect.min.x
+                            } else if rect.max.x < focused_node_rect.min.x {
+                                focused_node_rect.max.x - rect.min.x
+                            } else {
+                                0.0
+                            }
+                        },
+                    },
+                ))
+            })
+            .min_by(|(_, a), (_, b)| {
+                if a.max_y < b.min_y && b.max_y < a.min_y {
+                    a.x_drift.partial_cmp(&b.x_drift).unwrap()
+                } else {
+                    (a.min_y + a.x_drift)
+                        .partial_cmp(&(b.min_y + b.x_drift))
+                        .unwrap()
+                }
+            })
+            .map(|(id, _)| id)
+    } else {
+        transformed_nodes
+            .map(|(id, rect)| (id, (rect.min.y, rect.min.x)))
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+            .map(|(id, _)| id)
+    }
+}
+
 pub enum KbgpNavCommand {
     /// Move the focus one widget up. If no widget has the focus - move up from the bottom.
     ///
@@ -481,6 +1226,19 @@ pub enum KbgpNavCommand {
     NavigateRight,
     /// Make egui think the player clicked on the focused widget.
     Click,
+    /// Switch every [`kbgp_tab_bar`](crate::KbgpEguiUiExt::kbgp_tab_bar) to its previous tab.
+    PrevTab,
+    /// Switch every [`kbgp_tab_bar`](crate::KbgpEguiUiExt::kbgp_tab_bar) to its next tab.
+    NextTab,
+    /// Turn the virtual cursor on or off. See
+    /// [`KbgpVirtualCursorBinding`](crate::KbgpVirtualCursorBinding).
+    ToggleVirtualCursor,
+    /// Go back to the previous menu pushed with
+    /// [`kbgp_push_menu`](crate::KbgpEguiUiCtxExt::kbgp_push_menu). The game should check
+    /// [`kbgp_back`](crate::KbgpEguiUiCtxExt::kbgp_back) to show that menu.
+    ///
+    /// Does nothing in the root menu. Unlike navigation, holding it does not repeat.
+    Back,
     /// Activeate a user defined command.
     ///
     /// This variant is tricky to construct directly - use [`KbgpNavCommand::user`] instead.
@@ -576,6 +1334,10 @@ pub struct KbgpNavBindings {
     /// These are not limited to a specific gamepad, and are for buttons only - the axis behavior
     /// is hard coded. Note that in some environments the d-pad is treated as an axis.
     pub gamepad_buttons: HashMap<GamepadButton, KbgpNavCommand>,
+    /// The gamepad axes used for scrolling.
+    pub scroll: KbgpScrollBinding,
+    /// The gamepad axes used for moving the virtual cursor.
+    pub virtual_cursor: KbgpVirtualCursorBinding,
 }
 
 impl Default for KbgpNavBindings {
@@ -583,22 +1345,31 @@ impl Default for KbgpNavBindings {
     ///
     /// * Navigation: arrow keys, d-pad, left stick.
     /// * Activateion: Enter (egui builtin), Spacebar (also egui builtin), gamepad south button.
+    /// * Tab switching: Q/E, gamepad bumpers.
+    /// * Scrolling: right stick.
+    /// * Virtual cursor (while it is active): left stick.
     fn default() -> Self {
         Self::empty()
             .with_arrow_keys_navigation()
             .with_gamepad_dpad_navigation_and_south_button_activation()
+            .with_qe_tab_switching()
+            .with_gamepad_bumpers_tab_switching()
+            .with_scroll(KbgpScrollBinding::right_stick())
+            .with_virtual_cursor(KbgpVirtualCursorBinding::left_stick())
     }
 }
 
 impl KbgpNavBindings {
     /// Create empty bindings with no mapping.
     ///
-    /// Gamepad axes will still be mapped, because their handling is hard coded. Note that in some
+    /// The left stick will still navigate, because its handling is hard coded. Note that in some
     /// environments the d-pad is treated as an axis.
     pub fn empty() -> Self {
         Self {
             keyboard: Default::default(),
             gamepad_buttons: Default::default(),
+            scroll: Default::default(),
+            virtual_cursor: Default::default(),
         }
     }
 
@@ -606,12 +1377,19 @@ impl KbgpNavBindings {
     ///
     /// * Navigation: d-pad, left stick.
     /// * Activateion: gamepad south button.
+    /// * Tab switching: gamepad bumpers.
+    /// * Scrolling: right stick.
+    /// * Virtual cursor (while it is active): left stick.
     ///
     /// Use this instead of [`default`](Self::default) when
     /// [`disable_default_navigation`](crate::KbgpSettings::disable_default_navigation) is not set,
     /// otherwise both egui and KBGP will process the arrow keys, resulting in double-movement.
     pub fn default_gamepad_only() -> Self {
-        Self::empty().with_gamepad_dpad_navigation_and_south_button_activation()
+        Self::empty()
+            .with_gamepad_dpad_navigation_and_south_button_activation()
+            .with_gamepad_bumpers_tab_switching()
+            .with_scroll(KbgpScrollBinding::right_stick())
+            .with_virtual_cursor(KbgpVirtualCursorBinding::left_stick())
     }
 
     /// Bind the arrow keys for navigation.
@@ -651,6 +1429,68 @@ impl KbgpNavBindings {
         self
     }
 
+    /// Bind Q and E for switching tabs.
+    ///
+    /// [`KbgpNavBindings::default`] already contains these mappings.
+    pub fn bind_qe_tab_switching(&mut self) {
+        self.bind_key(KeyCode::KeyQ, KbgpNavCommand::PrevTab);
+        self.bind_key(KeyCode::KeyE, KbgpNavCommand::NextTab);
+    }
+
+    /// Bind Q and E for switching tabs.
+    ///
+    /// [`KbgpNavBindings::default`] already contains these mappings.
+    pub fn with_qe_tab_switching(mut self) -> Self {
+        self.bind_qe_tab_switching();
+        self
+    }
+
+    /// Bind the gamepad's bumpers (LB/RB) for switching tabs.
+    ///
+    /// [`KbgpNavBindings::default`] already contains these mappings.
+    pub fn bind_gamepad_bumpers_tab_switching(&mut self) {
+        self.bind_gamepad_button(GamepadButton::LeftTrigger, KbgpNavCommand::PrevTab);
+        self.bind_gamepad_button(GamepadButton::RightTrigger, KbgpNavCommand::NextTab);
+    }
+
+    /// Bind the gamepad's bumpers (LB/RB) for switching tabs.
+    ///
+    /// [`KbgpNavBindings::default`] already contains these mappings.
+    pub fn with_gamepad_bumpers_tab_switching(mut self) -> Self {
+        self.bind_gamepad_bumpers_tab_switching();
+        self
+    }
+
+    /// Bind pressing the gamepad's left stick (L3) for toggling the virtual cursor.
+    pub fn bind_gamepad_left_thumb_virtual_cursor_toggle(&mut self) {
+        self.bind_gamepad_button(
+            GamepadButton::LeftThumb,
+            KbgpNavCommand::ToggleVirtualCursor,
+        );
+    }
+
+    /// Bind pressing the gamepad's left stick (L3) for toggling the virtual cursor.
+    pub fn with_gamepad_left_thumb_virtual_cursor_toggle(mut self) -> Self {
+        self.bind_gamepad_left_thumb_virtual_cursor_toggle();
+        self
+    }
+
+    /// Bind Escape and the gamepad's east button for going back to the previous menu.
+    ///
+    /// See [`KbgpNavCommand::Back`].
+    pub fn bind_escape_and_gamepad_east_back(&mut self) {
+        self.bind_key(KeyCode::Escape, KbgpNavCommand::Back);
+        self.bind_gamepad_button(GamepadButton::East, KbgpNavCommand::Back);
+    }
+
+    /// Bind Escape and the gamepad's east button for going back to the previous menu.
+    ///
+    /// See [`KbgpNavCommand::Back`].
+    pub fn with_escape_and_gamepad_east_back(mut self) -> Self {
+        self.bind_escape_and_gamepad_east_back();
+        self
+    }
+
     /// Bind WASD for navigation.
     pub fn bind_wasd_navigation(&mut self) {
         self.bind_key(KeyCode::KeyW, KbgpNavCommand::NavigateUp);
@@ -676,6 +1516,28 @@ impl KbgpNavBindings {
         self
     }
 
+    /// Set the gamepad axes used for scrolling.
+    pub fn bind_scroll(&mut self, scroll: KbgpScrollBinding) {
+        self.scroll = scroll;
+    }
+
+    /// Set the gamepad axes used for scrolling.
+    pub fn with_scroll(mut self, scroll: KbgpScrollBinding) -> Self {
+        self.bind_scroll(scroll);
+        self
+    }
+
+    /// Set the gamepad axes used for moving the virtual cursor.
+    pub fn bind_virtual_cursor(&mut self, virtual_cursor: KbgpVirtualCursorBinding) {
+        self.virtual_cursor = virtual_cursor;
+    }
+
+    /// Set the gamepad axes used for moving the virtual cursor.
+    pub fn with_virtual_cursor(mut self, virtual_cursor: KbgpVirtualCursorBinding) -> Self {
+        self.bind_virtual_cursor(virtual_cursor);
+        self
+    }
+
     /// Bind a command to a gamepad button.
     pub fn bind_gamepad_button(&mut self, gamepad_button: GamepadButton, command: KbgpNavCommand) {
         self.gamepad_buttons.insert(gamepad_button, command);
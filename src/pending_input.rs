@@ -1,13 +1,89 @@
 use crate::egui;
+use bevy::input::gamepad::GamepadInput;
 use bevy::platform::collections::HashSet;
 use bevy::prelude::*;
 
-use crate::{KbgpCommon, KbgpInput};
+use crate::glyphs::{show_axis_glyph, show_chord_glyphs};
+use crate::{KbgpAxisInput, KbgpCommon, KbgpInput, KbgpInputFormatter};
+
+/// Axes with an absolute value below this are considered to be at rest.
+const AXIS_REST_THRESHOLD: f32 = 0.1;
+
+/// The lowest threshold for selecting an axis, so that an axis at rest will not be selected (or
+/// considered still tilted).
+pub(crate) const MIN_AXIS_THRESHOLD: f32 = 2.0 * AXIS_REST_THRESHOLD;
+
+/// Where the prompt of [`KbgpPendingInputPrompt`] is shown.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum KbgpPendingInputPromptPlacement {
+    /// In a tooltip next to the widget that accepts the input.
+    #[default]
+    Tooltip,
+    /// In a window at the center of the screen, above everything else.
+    Overlay,
+    /// Over the widget that accepts the input, hiding its content.
+    Inline,
+}
+
+/// Configuration for the prompt shown while the player enters input.
+///
+/// Install it with
+/// [`kbgp_set_pending_input_prompt`](crate::KbgpEguiUiCtxExt::kbgp_set_pending_input_prompt). Used
+/// by the built in input setting methods (like
+/// [`kbgp_pending_input`](crate::KbgpEguiResponseExt::kbgp_pending_input)) and by
+/// [`KbgpInputManualHandle::show_current_chord`] and
+/// [`KbgpInputManualHandle::show_current_axis`].
+///
+/// ```no_run
+/// # use bevy_egui_kbgp::egui;
+/// # use bevy_egui_kbgp::prelude::*;
+/// # use bevy_egui_kbgp::{KbgpPendingInputPrompt, KbgpPendingInputPromptPlacement};
+/// # let ui: egui::Ui = todo!();
+/// ui.kbgp_set_pending_input_prompt(KbgpPendingInputPrompt {
+///     placement: KbgpPendingInputPromptPlacement::Overlay,
+///     text: Some("Press a key...".to_owned()),
+///     timeout: Some(5.0),
+///     ..Default::default()
+/// });
+/// ```
+#[derive(Clone)]
+pub struct KbgpPendingInputPrompt {
+    /// Where to show the prompt.
+    pub placement: KbgpPendingInputPromptPlacement,
+    /// Text to show before the player enters any input.
+    pub text: Option<String>,
+    /// Cancel the input setting if the player does not finish entering input after that many
+    /// seconds.
+    pub timeout: Option<f64>,
+    /// Whether or not to show how many seconds are left until the
+    /// [`timeout`](Self::timeout).
+    pub show_countdown: bool,
+    /// Whether or not to show the input as glyphs, if a glyph atlas was set with
+    /// [`kbgp_set_glyph_atlas`](crate::KbgpEguiUiCtxExt::kbgp_set_glyph_atlas).
+    pub use_glyphs: bool,
+    /// The maximum width of the prompt. Not used for
+    /// [`Inline`](KbgpPendingInputPromptPlacement::Inline), which takes the width of the widget.
+    pub max_width: f32,
+}
+
+impl Default for KbgpPendingInputPrompt {
+    fn default() -> Self {
+        Self {
+            placement: Default::default(),
+            text: None,
+            timeout: None,
+            show_countdown: true,
+            use_glyphs: true,
+            max_width: 100.0,
+        }
+    }
+}
 
 /// Handle for
 /// [`kbgp_pending_input_manual`](crate::KbgpEguiResponseExt::kbgp_pending_input_manual).
 pub struct KbgpInputManualHandle<'a> {
     pub(crate) state: &'a mut KbgpPendingInputState,
+    pub(crate) common: &'a KbgpCommon,
 }
 
 impl<'a> KbgpInputManualHandle<'a> {
@@ -28,7 +104,8 @@ impl<'a> KbgpInputManualHandle<'a> {
     /// * `should_add` can be used to decided which input to add based on the nature of that new
     ///   input and on already existing input.
     /// * When adding a positive gamepad axis, if the negative input of the same axis was
-    ///   previously added it will be removed - and vice versa.
+    ///   previously added it will be removed - and vice versa. The same goes for opposite
+    ///   directions of the mouse wheel and the mouse motion.
     pub fn process_new_input(&mut self, mut should_add: impl FnMut(&Self, KbgpInput) -> bool) {
         for input in self.state.input_this_frame.iter() {
             if should_add(self, input.clone()) {
@@ -57,6 +134,24 @@ impl<'a> KbgpInputManualHandle<'a> {
                     KbgpInput::MouseWheelRight => {
                         self.state.received_input.remove(&KbgpInput::MouseWheelLeft);
                     }
+                    KbgpInput::MouseMotionUp => {
+                        self.state
+                            .received_input
+                            .remove(&KbgpInput::MouseMotionDown);
+                    }
+                    KbgpInput::MouseMotionDown => {
+                        self.state.received_input.remove(&KbgpInput::MouseMotionUp);
+                    }
+                    KbgpInput::MouseMotionLeft => {
+                        self.state
+                            .received_input
+                            .remove(&KbgpInput::MouseMotionRight);
+                    }
+                    KbgpInput::MouseMotionRight => {
+                        self.state
+                            .received_input
+                            .remove(&KbgpInput::MouseMotionLeft);
+                    }
                     _ => {}
                 }
                 self.state.received_input.insert(input.clone());
@@ -64,18 +159,167 @@ impl<'a> KbgpInputManualHandle<'a> {
         }
     }
 
+    /// All the analog axes reported during this frame, together with their values.
+    ///
+    /// Does not include axes that were already tilted when the input setting was activated, until
+    /// they return to rest.
+    pub fn axes_this_frame(&'a self) -> impl 'a + Iterator<Item = (KbgpAxisInput, f32)> {
+        self.state.axes_this_frame.iter().cloned()
+    }
+
+    /// The analog axis already received so far.
+    pub fn received_axis(&self) -> Option<&KbgpAxisInput> {
+        self.state.received_axis.as_ref()
+    }
+
+    /// Set an axis from `axes_this_frame` as the `received_axis`.
+    ///
+    /// * Only axes tilted at least `threshold` (in absolute value) are considered.
+    /// * `should_add` can be used to decided which axis to accept based on the nature of that new
+    ///   axis and on the already received axis.
+    pub fn process_new_axis(
+        &mut self,
+        threshold: f32,
+        mut should_add: impl FnMut(&Self, KbgpAxisInput) -> bool,
+    ) {
+        for (axis, value) in self.state.axes_this_frame.iter() {
+            if threshold <= value.abs() && should_add(self, axis.clone()) {
+                self.state.received_axis = Some(axis.clone());
+            }
+        }
+    }
+
+    /// The formatter set by
+    /// [`kbgp_set_input_formatter`](crate::KbgpEguiUiCtxExt::kbgp_set_input_formatter).
+    pub fn formatter(&self) -> &dyn KbgpInputFormatter {
+        self.common.input_formatter.as_ref()
+    }
+
     /// Format A string representing the currently received input.
     pub fn format_current_chord(&self) -> String {
-        KbgpInput::format_chord(self.received_input().iter().cloned())
+        KbgpInput::format_chord_with(self.formatter(), self.received_input().iter().cloned())
     }
 
-    /// Show a tooltip of the currently received input.
+    /// Show a prompt with the currently received input.
+    ///
+    /// The prompt is configured by
+    /// [`kbgp_set_pending_input_prompt`](crate::KbgpEguiUiCtxExt::kbgp_set_pending_input_prompt).
     pub fn show_current_chord(&self, response: &egui::Response) {
-        response.show_tooltip_ui(|ui| {
-            ui.set_max_width(100.0); // TODO: make this more automatic?
-            ui.label(self.format_current_chord());
+        let has_input = !self.received_input().is_empty();
+        self.show_prompt(response, has_input, |ui| {
+            if self.prompt().use_glyphs && self.common.glyph_atlas.is_some() {
+                show_chord_glyphs(ui, self.common, self.received_input().iter().cloned());
+            } else {
+                ui.label(self.format_current_chord());
+            }
+        });
+    }
+
+    /// Show the currently received input as glyphs, inside some custom UI.
+    ///
+    /// Inputs without glyphs (or all inputs, if there is no glyph atlas) are shown as text.
+    pub fn show_current_chord_glyphs(&self, ui: &mut egui::Ui) -> egui::Response {
+        show_chord_glyphs(ui, self.common, self.received_input().iter().cloned())
+    }
+
+    /// Format A string representing the currently received axis.
+    pub fn format_current_axis(&self) -> String {
+        if let Some(axis) = self.received_axis() {
+            self.formatter().format_axis(axis)
+        } else {
+            String::new()
+        }
+    }
+
+    /// Show a prompt with the currently received axis.
+    ///
+    /// The prompt is configured by
+    /// [`kbgp_set_pending_input_prompt`](crate::KbgpEguiUiCtxExt::kbgp_set_pending_input_prompt).
+    pub fn show_current_axis(&self, response: &egui::Response) {
+        let has_input = self.received_axis().is_some();
+        self.show_prompt(response, has_input, |ui| {
+            if self.prompt().use_glyphs {
+                if let Some(axis) = self.received_axis() {
+                    show_axis_glyph(ui, self.common, axis);
+                }
+            } else {
+                ui.label(self.format_current_axis());
+            }
         });
     }
+
+    /// The prompt configuration set by
+    /// [`kbgp_set_pending_input_prompt`](crate::KbgpEguiUiCtxExt::kbgp_set_pending_input_prompt).
+    pub fn prompt(&self) -> &KbgpPendingInputPrompt {
+        &self.common.pending_input_prompt
+    }
+
+    /// How many seconds passed since the input setting was activated.
+    pub fn elapsed_secs(&self) -> f64 {
+        self.state.elapsed_secs()
+    }
+
+    /// How many seconds are left until the input setting is cancelled, or `None` if the
+    /// [`prompt`](Self::prompt) has no timeout.
+    pub fn secs_left(&self) -> Option<f64> {
+        let timeout = self.prompt().timeout?;
+        Some((timeout - self.elapsed_secs()).max(0.0))
+    }
+
+    fn show_prompt(
+        &self,
+        response: &egui::Response,
+        has_input: bool,
+        show_input: impl FnOnce(&mut egui::Ui),
+    ) {
+        let prompt = self.prompt();
+        let add_contents = |ui: &mut egui::Ui| {
+            match (&prompt.text, has_input) {
+                (Some(text), false) => {
+                    ui.label(text);
+                }
+                _ => show_input(ui),
+            }
+            if prompt.show_countdown {
+                if let Some(secs_left) = self.secs_left() {
+                    ui.label(format!("{}s", secs_left.ceil()));
+                }
+            }
+        };
+        match prompt.placement {
+            KbgpPendingInputPromptPlacement::Tooltip => {
+                response.show_tooltip_ui(|ui| {
+                    ui.set_max_width(prompt.max_width);
+                    add_contents(ui);
+                });
+            }
+            KbgpPendingInputPromptPlacement::Overlay => {
+                egui::Area::new(response.id.with("kbgp_pending_input_prompt"))
+                    .order(egui::Order::Foreground)
+                    .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                    .show(&response.ctx, |ui| {
+                        egui::Frame::popup(ui.style()).show(ui, |ui| {
+                            ui.set_max_width(prompt.max_width);
+                            add_contents(ui);
+                        });
+                    });
+            }
+            KbgpPendingInputPromptPlacement::Inline => {
+                let mut ui = egui::Ui::new(
+                    response.ctx.clone(),
+                    response.id.with("kbgp_pending_input_prompt"),
+                    egui::UiBuilder::new()
+                        .layer_id(response.layer_id)
+                        .max_rect(response.rect),
+                );
+                ui.set_clip_rect(response.rect);
+                let visuals = ui.visuals().widgets.active;
+                ui.painter()
+                    .rect_filled(response.rect, visuals.corner_radius, visuals.bg_fill);
+                ui.horizontal_centered(add_contents);
+            }
+        }
+    }
 }
 
 pub(crate) struct KbgpPendingInputState {
@@ -83,6 +327,11 @@ pub(crate) struct KbgpPendingInputState {
     input_this_frame: Vec<KbgpInput>,
     ignored_input: Option<HashSet<KbgpInput>>,
     received_input: HashSet<KbgpInput>,
+    axes_this_frame: Vec<(KbgpAxisInput, f32)>,
+    ignored_axes: Option<HashSet<KbgpAxisInput>>,
+    received_axis: Option<KbgpAxisInput>,
+    started_at: Option<f64>,
+    current_time: f64,
 }
 
 impl KbgpPendingInputState {
@@ -92,17 +341,33 @@ impl KbgpPendingInputState {
             input_this_frame: Default::default(),
             ignored_input: None,
             received_input: Default::default(),
+            axes_this_frame: Default::default(),
+            ignored_axes: None,
+            received_axis: None,
+            started_at: None,
+            current_time: 0.0,
+        }
+    }
+
+    pub(crate) fn elapsed_secs(&self) -> f64 {
+        if let Some(started_at) = self.started_at {
+            self.current_time - started_at
+        } else {
+            0.0
         }
     }
 
     pub(crate) fn prepare(
         &mut self,
-        _common: &KbgpCommon,
-        _egui_ctx: &egui::Context,
+        current_time: f64,
         prepare_dlg: impl FnOnce(&mut KbgpPreparePendingInput),
     ) {
+        self.current_time = current_time;
+        self.started_at.get_or_insert(self.current_time);
         let mut handle = KbgpPreparePendingInput {
             current_input: Vec::new(),
+            current_axes: Vec::new(),
+            mouse_motion: Vec2::ZERO,
         };
         prepare_dlg(&mut handle);
         if let Some(ignored_input) = self.ignored_input.as_mut() {
@@ -116,12 +381,37 @@ impl KbgpPendingInputState {
         } else {
             self.ignored_input = Some(handle.current_input.iter().cloned().collect());
         }
+        if let Some(ignored_axes) = self.ignored_axes.as_mut() {
+            ignored_axes.retain(|axis| {
+                handle
+                    .current_axes
+                    .iter()
+                    .any(|(inp, value)| inp == axis && AXIS_REST_THRESHOLD <= value.abs())
+            });
+            self.axes_this_frame = handle
+                .current_axes
+                .iter()
+                .filter(|(axis, _)| !ignored_axes.contains(axis))
+                .cloned()
+                .collect();
+        } else {
+            self.ignored_axes = Some(
+                handle
+                    .current_axes
+                    .iter()
+                    .filter(|(_, value)| AXIS_REST_THRESHOLD <= value.abs())
+                    .map(|(axis, _)| axis.clone())
+                    .collect(),
+            );
+        }
     }
 }
 
 /// An option of [`KbgpPrepare`](crate::KbgpPrepare).
 pub struct KbgpPreparePendingInput {
-    current_input: Vec<KbgpInput>,
+    pub(crate) current_input: Vec<KbgpInput>,
+    pub(crate) current_axes: Vec<(KbgpAxisInput, f32)>,
+    mouse_motion: Vec2,
 }
 
 impl KbgpPreparePendingInput {
@@ -143,6 +433,14 @@ impl KbgpPreparePendingInput {
         self.current_input.extend(inputs);
     }
 
+    /// Notify KBGP about the current value of an analog axis.
+    ///
+    /// Unlike [`accept_input`](Self::accept_input), this should be called even when the axis is at
+    /// rest, so that KBGP can tell when the player lets go of it.
+    pub fn accept_axis_input(&mut self, axis: KbgpAxisInput, value: f32) {
+        self.current_axes.push((axis, value));
+    }
+
     /// Notify KBGP about all the input from the keyboard.
     pub fn accept_keyboard_input(&mut self, keys: &ButtonInput<KeyCode>) {
         self.accept_inputs(keys.get_pressed().copied().map(KbgpInput::Keyboard));
@@ -176,7 +474,53 @@ impl KbgpPreparePendingInput {
         }
     }
 
+    /// Notify KBGP about mouse motion.
+    ///
+    /// The motion of all the events in the frame is accumulated, and a direction is accepted (as
+    /// [`KbgpInput::MouseMotionUp`] etc.) once the mouse moved at least `threshold` pixels in it.
+    /// The whole axes ([`KbgpAxisInput::MouseMotionX`] and [`KbgpAxisInput::MouseMotionY`]) are
+    /// reported scaled so that `threshold` pixels correspond to a value of 1.0.
+    ///
+    /// A `threshold` below 1 pixel (or NaN) is treated as 1 pixel.
+    pub fn accept_mouse_motion_event(
+        &mut self,
+        event: &bevy::input::mouse::MouseMotion,
+        threshold: f32,
+    ) {
+        let threshold = threshold.max(1.0);
+        self.mouse_motion += event.delta;
+        let Vec2 { x, y } = self.mouse_motion;
+        if threshold <= x {
+            self.accept_input_unique(KbgpInput::MouseMotionRight);
+        } else if x <= -threshold {
+            self.accept_input_unique(KbgpInput::MouseMotionLeft);
+        }
+        // Bevy's mouse motion Y axis points downward.
+        if threshold <= y {
+            self.accept_input_unique(KbgpInput::MouseMotionDown);
+        } else if y <= -threshold {
+            self.accept_input_unique(KbgpInput::MouseMotionUp);
+        }
+        for (axis, value) in [
+            (KbgpAxisInput::MouseMotionX, x / threshold),
+            (KbgpAxisInput::MouseMotionY, -y / threshold),
+        ] {
+            if let Some((_, existing_value)) =
+                self.current_axes.iter_mut().find(|(inp, _)| *inp == axis)
+            {
+                *existing_value = value;
+            } else {
+                self.accept_axis_input(axis, value);
+            }
+        }
+    }
+
     /// Notify KBGP about all the input from the gamepad.
+    ///
+    /// This includes both the discretized button-like input (see
+    /// [`kbgp_pending_input`](crate::KbgpEguiResponseExt::kbgp_pending_input)) and the full values
+    /// of all the gamepad's axes (see
+    /// [`kbgp_pending_axis`](crate::KbgpEguiResponseExt::kbgp_pending_axis)).
     pub fn accept_gamepad_input(&mut self, gamepad_entity: Entity, gamepad: &Gamepad) {
         self.accept_inputs(
             gamepad
@@ -200,5 +544,22 @@ impl KbgpPreparePendingInput {
                 }
             }
         }
+        self.accept_gamepad_axes_input(gamepad_entity, gamepad);
+    }
+
+    /// Notify KBGP about the full values of all the gamepad's analog axes.
+    ///
+    /// Already called by [`accept_gamepad_input`](Self::accept_gamepad_input).
+    pub fn accept_gamepad_axes_input(&mut self, gamepad_entity: Entity, gamepad: &Gamepad) {
+        for gamepad_input in gamepad.get_analog_axes() {
+            if let GamepadInput::Axis(gamepad_axis) = gamepad_input {
+                if let Some(axis_value) = gamepad.get(*gamepad_axis) {
+                    self.accept_axis_input(
+                        KbgpAxisInput::GamepadAxis(gamepad_entity, *gamepad_axis),
+                        axis_value,
+                    );
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,536 @@
+use std::any::Any;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::egui;
+use crate::{kbgp_get, KbgpAxisInput, KbgpInput, KbgpNavCommand};
+use crate::{KbgpPrepareNavigation, KbgpPreparePendingInput};
+
+/// A navigation command, as stored in a [`KbgpRecording`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum KbgpRecordedCommand {
+    NavigateUp,
+    NavigateDown,
+    NavigateLeft,
+    NavigateRight,
+    Click,
+    PrevTab,
+    NextTab,
+    ToggleVirtualCursor,
+    Back,
+    /// A user action, by the name it was registered with in [`KbgpRecordedUserActions`].
+    User(String),
+}
+
+/// The input KBGP received in a single frame.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KbgpRecordedFrame {
+    /// When the frame started, in seconds since the first recorded frame.
+    pub time: f64,
+    pub input: KbgpRecordedInput,
+}
+
+/// The input of a [`KbgpRecordedFrame`], which depends on what KBGP was doing in that frame.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum KbgpRecordedInput {
+    /// The commands held while KBGP was navigating the UI.
+    Navigation(Vec<KbgpRecordedCommand>),
+    /// The inputs held while KBGP was waiting for the player to enter input.
+    PendingInput {
+        inputs: Vec<KbgpInput>,
+        axes: Vec<(KbgpAxisInput, f32)>,
+    },
+}
+
+/// Navigation input recorded with [`kbgp_start_recording`], for replaying with
+/// [`kbgp_start_replay`].
+///
+/// Only the abstract commands are recorded - not the keys and buttons that triggered them - so the
+/// replay does not depend on the keyboard layout or on the bindings. Gamepad entities in pending
+/// inputs are replaced by the order in which the gamepads first appeared in the recording, and are
+/// mapped back with [`KbgpReplayer::with_gamepads`].
+///
+/// Recordings are stored as [RON](https://github.com/ron-rs/ron) files.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct KbgpRecording {
+    pub frames: Vec<KbgpRecordedFrame>,
+}
+
+impl KbgpRecording {
+    /// Serialize the recording to a RON string.
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, Default::default())
+    }
+
+    /// Deserialize a recording from a RON string.
+    pub fn from_ron(text: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(text)
+    }
+
+    /// Save the recording to a RON file.
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let text = self.to_ron().map_err(std::io::Error::other)?;
+        std::fs::write(path, text)
+    }
+
+    /// Load a recording from a RON file.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::from_ron(&text)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    }
+}
+
+type UserActionMatcher = dyn Fn(&(dyn Any + Send + Sync)) -> bool + Send + Sync;
+
+struct NamedUserAction {
+    name: String,
+    matches: Arc<UserActionMatcher>,
+    command: Arc<dyn Fn() -> KbgpNavCommand + Send + Sync>,
+}
+
+/// Names for the user actions of [`KbgpNavCommand::user`], so that they can be recorded.
+///
+/// User actions that were not registered are left out of the recording.
+#[derive(Default)]
+pub struct KbgpRecordedUserActions {
+    actions: Vec<NamedUserAction>,
+}
+
+impl KbgpRecordedUserActions {
+    /// Register a user action under a name.
+    pub fn with_user_action<T: 'static + Clone + PartialEq + Send + Sync>(
+        mut self,
+        name: impl Into<String>,
+        action: T,
+    ) -> Self {
+        let matched_action = action.clone();
+        self.actions.push(NamedUserAction {
+            name: name.into(),
+            matches: Arc::new(move |applied| applied.downcast_ref::<T>() == Some(&matched_action)),
+            command: Arc::new(move || KbgpNavCommand::user(action.clone())),
+        });
+        self
+    }
+
+    fn name_of(&self, applied: &(dyn Any + Send + Sync)) -> Option<&str> {
+        self.actions
+            .iter()
+            .find(|action| (action.matches)(applied))
+            .map(|action| action.name.as_str())
+    }
+
+    fn command_of(&self, name: &str) -> Option<KbgpNavCommand> {
+        self.actions
+            .iter()
+            .find(|action| action.name == name)
+            .map(|action| (action.command)())
+    }
+}
+
+/// Records the input KBGP receives. Started with [`kbgp_start_recording`].
+#[derive(Default)]
+pub struct KbgpRecorder {
+    user_actions: KbgpRecordedUserActions,
+    gamepads: Vec<Entity>,
+    started_at: Option<f64>,
+    recording: KbgpRecording,
+}
+
+impl KbgpRecorder {
+    /// Create a recorder that can name the given user actions.
+    pub fn new(user_actions: KbgpRecordedUserActions) -> Self {
+        Self {
+            user_actions,
+            ..Default::default()
+        }
+    }
+
+    fn normalize_gamepad(&mut self, entity: Entity) -> Entity {
+        let index = if let Some(index) = self.gamepads.iter().position(|e| *e == entity) {
+            index
+        } else {
+            self.gamepads.push(entity);
+            self.gamepads.len() - 1
+        };
+        Entity::from_raw(index as u32)
+    }
+
+    fn record_frame(&mut self, time: f64, input: KbgpRecordedInput) {
+        let started_at = *self.started_at.get_or_insert(time);
+        self.recording.frames.push(KbgpRecordedFrame {
+            time: time - started_at,
+            input,
+        });
+    }
+
+    fn record_navigation(&mut self, time: f64, prp: &KbgpPrepareNavigation) {
+        let mut commands = prp
+            .applied_commands()
+            .map(|command| match command {
+                KbgpNavCommand::NavigateUp => KbgpRecordedCommand::NavigateUp,
+                KbgpNavCommand::NavigateDown => KbgpRecordedCommand::NavigateDown,
+                KbgpNavCommand::NavigateLeft => KbgpRecordedCommand::NavigateLeft,
+                KbgpNavCommand::NavigateRight => KbgpRecordedCommand::NavigateRight,
+                KbgpNavCommand::Click => KbgpRecordedCommand::Click,
+                KbgpNavCommand::PrevTab => KbgpRecordedCommand::PrevTab,
+                KbgpNavCommand::NextTab => KbgpRecordedCommand::NextTab,
+                KbgpNavCommand::ToggleVirtualCursor => KbgpRecordedCommand::ToggleVirtualCursor,
+                KbgpNavCommand::Back => KbgpRecordedCommand::Back,
+                KbgpNavCommand::User(_) => unreachable!("user actions are recorded separately"),
+            })
+            .collect::<Vec<_>>();
+        if let Some(name) = prp
+            .applied_user_action()
+            .and_then(|action| self.user_actions.name_of(action))
+        {
+            commands.push(KbgpRecordedCommand::User(name.to_owned()));
+        }
+        self.record_frame(time, KbgpRecordedInput::Navigation(commands));
+    }
+
+    fn record_pending_input(&mut self, time: f64, prp: &KbgpPreparePendingInput) {
+        let inputs = prp
+            .current_input
+            .iter()
+            .map(|input| match input {
+                KbgpInput::GamepadAxisPositive(entity, axis) => {
+                    KbgpInput::GamepadAxisPositive(self.normalize_gamepad(*entity), *axis)
+                }
+                KbgpInput::GamepadAxisNegative(entity, axis) => {
+                    KbgpInput::GamepadAxisNegative(self.normalize_gamepad(*entity), *axis)
+                }
+                KbgpInput::GamepadButton(entity, button) => {
+                    KbgpInput::GamepadButton(self.normalize_gamepad(*entity), *button)
+                }
+                input => input.clone(),
+            })
+            .collect();
+        let axes = prp
+            .current_axes
+            .iter()
+            .map(|(axis, value)| match axis {
+                KbgpAxisInput::GamepadAxis(entity, axis) => (
+                    KbgpAxisInput::GamepadAxis(self.normalize_gamepad(*entity), *axis),
+                    *value,
+                ),
+                axis => (axis.clone(), *value),
+            })
+            .collect();
+        self.record_frame(time, KbgpRecordedInput::PendingInput { inputs, axes });
+    }
+}
+
+/// Feeds a [`KbgpRecording`] to KBGP instead of the real input. Started with
+/// [`kbgp_start_replay`].
+///
+/// The replay advances one recorded frame per frame. KBGP uses the recorded time of each frame
+/// instead of the real time, so keys held for multiple frames repeat exactly as they did during
+/// the recording, regardless of the frame rate.
+///
+/// Once all the frames were replayed, KBGP goes back to using the real input. If a frame was
+/// recorded while KBGP was doing something else than it does when replaying it (e.g. waiting for
+/// the player to enter input rather than navigating the UI) the UI went out of sync with the
+/// recording - the replay stops, and [`kbgp_take_replay_error`] reports the frame.
+pub struct KbgpReplayer {
+    user_actions: KbgpRecordedUserActions,
+    gamepads: Vec<Entity>,
+    frames: VecDeque<KbgpRecordedFrame>,
+    replayed_frames: usize,
+    time_offset: Option<f64>,
+}
+
+impl KbgpReplayer {
+    /// Create a replayer that can recreate the given user actions.
+    ///
+    /// Recorded user actions that are not registered in `user_actions` are skipped.
+    pub fn new(recording: KbgpRecording, user_actions: KbgpRecordedUserActions) -> Self {
+        Self {
+            user_actions,
+            gamepads: Vec::new(),
+            frames: recording.frames.into(),
+            replayed_frames: 0,
+            time_offset: None,
+        }
+    }
+
+    /// Map the gamepads of the recording, by the order they appeared in it, to actual gamepads.
+    ///
+    /// Gamepads without a mapping are replayed with `Entity::from_raw(index)`.
+    pub fn with_gamepads(mut self, gamepads: Vec<Entity>) -> Self {
+        self.gamepads = gamepads;
+        self
+    }
+
+    fn denormalize_gamepad(&self, entity: Entity) -> Entity {
+        self.gamepads
+            .get(entity.index() as usize)
+            .copied()
+            .unwrap_or(entity)
+    }
+
+    /// The time of the next frame, on the same timeline as the real time of the first replayed
+    /// frame.
+    fn frame_time(&mut self, real_time: f64) -> Option<f64> {
+        let recorded_time = self.frames.front()?.time;
+        let time_offset = *self.time_offset.get_or_insert(real_time - recorded_time);
+        Some(time_offset + recorded_time)
+    }
+
+    fn replay_navigation(
+        &self,
+        commands: Vec<KbgpRecordedCommand>,
+        prp: &mut KbgpPrepareNavigation,
+    ) {
+        for command in commands {
+            let command = match command {
+                KbgpRecordedCommand::NavigateUp => KbgpNavCommand::NavigateUp,
+                KbgpRecordedCommand::NavigateDown => KbgpNavCommand::NavigateDown,
+                KbgpRecordedCommand::NavigateLeft => KbgpNavCommand::NavigateLeft,
+                KbgpRecordedCommand::NavigateRight => KbgpNavCommand::NavigateRight,
+                KbgpRecordedCommand::Click => KbgpNavCommand::Click,
+                KbgpRecordedCommand::PrevTab => KbgpNavCommand::PrevTab,
+                KbgpRecordedCommand::NextTab => KbgpNavCommand::NextTab,
+                KbgpRecordedCommand::ToggleVirtualCursor => KbgpNavCommand::ToggleVirtualCursor,
+                KbgpRecordedCommand::Back => KbgpNavCommand::Back,
+                KbgpRecordedCommand::User(name) => {
+                    let Some(command) = self.user_actions.command_of(&name) else {
+                        continue;
+                    };
+                    command
+                }
+            };
+            prp.apply_action(&command);
+        }
+    }
+
+    fn replay_pending_input(
+        &self,
+        inputs: Vec<KbgpInput>,
+        axes: Vec<(KbgpAxisInput, f32)>,
+        prp: &mut KbgpPreparePendingInput,
+    ) {
+        prp.accept_inputs(inputs.into_iter().map(|input| match input {
+            KbgpInput::GamepadAxisPositive(entity, axis) => {
+                KbgpInput::GamepadAxisPositive(self.denormalize_gamepad(entity), axis)
+            }
+            KbgpInput::GamepadAxisNegative(entity, axis) => {
+                KbgpInput::GamepadAxisNegative(self.denormalize_gamepad(entity), axis)
+            }
+            KbgpInput::GamepadButton(entity, button) => {
+                KbgpInput::GamepadButton(self.denormalize_gamepad(entity), button)
+            }
+            input => input,
+        }));
+        for (axis, value) in axes {
+            let axis = match axis {
+                KbgpAxisInput::GamepadAxis(entity, axis) => {
+                    KbgpAxisInput::GamepadAxis(self.denormalize_gamepad(entity), axis)
+                }
+                axis => axis,
+            };
+            prp.accept_axis_input(axis, value);
+        }
+    }
+}
+
+/// Why a replay started with [`kbgp_start_replay`] stopped before all the frames were replayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KbgpReplayError {
+    /// The frame, by its index in [`KbgpRecording::frames`], was recorded while KBGP was navigating
+    /// the UI, but KBGP was waiting for the player to enter input when replaying it.
+    ExpectedNavigation { frame: usize },
+    /// The frame, by its index in [`KbgpRecording::frames`], was recorded while KBGP was waiting
+    /// for the player to enter input, but KBGP was navigating the UI when replaying it.
+    ExpectedPendingInput { frame: usize },
+}
+
+impl std::fmt::Display for KbgpReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KbgpReplayError::ExpectedNavigation { frame } => write!(
+                f,
+                "frame {frame} was recorded while navigating, but replayed while waiting for input"
+            ),
+            KbgpReplayError::ExpectedPendingInput { frame } => write!(
+                f,
+                "frame {frame} was recorded while waiting for input, but replayed while navigating"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for KbgpReplayError {}
+
+#[derive(Default)]
+pub(crate) enum KbgpRecordingState {
+    #[default]
+    Idle,
+    Recording(KbgpRecorder),
+    Replaying(KbgpReplayer),
+    Failed(KbgpReplayError),
+}
+
+impl KbgpRecordingState {
+    /// The time KBGP should use for the frame - the recorded time of the frame when replaying.
+    pub(crate) fn frame_time(&mut self, real_time: f64) -> f64 {
+        match self {
+            KbgpRecordingState::Replaying(replayer) => {
+                replayer.frame_time(real_time).unwrap_or(real_time)
+            }
+            _ => real_time,
+        }
+    }
+
+    pub(crate) fn prepare_navigation(
+        &mut self,
+        time: f64,
+        prp: &mut KbgpPrepareNavigation,
+        prepare_dlg: impl FnOnce(&mut KbgpPrepareNavigation),
+    ) {
+        match self {
+            KbgpRecordingState::Idle | KbgpRecordingState::Failed(_) => prepare_dlg(prp),
+            KbgpRecordingState::Recording(recorder) => {
+                prepare_dlg(prp);
+                recorder.record_navigation(time, prp);
+            }
+            KbgpRecordingState::Replaying(replayer) => match replayer.frames.pop_front() {
+                Some(KbgpRecordedFrame {
+                    input: KbgpRecordedInput::Navigation(commands),
+                    ..
+                }) => {
+                    replayer.replayed_frames += 1;
+                    replayer.replay_navigation(commands, prp);
+                }
+                Some(KbgpRecordedFrame {
+                    input: KbgpRecordedInput::PendingInput { .. },
+                    ..
+                }) => {
+                    *self = KbgpRecordingState::Failed(KbgpReplayError::ExpectedPendingInput {
+                        frame: replayer.replayed_frames,
+                    });
+                    prepare_dlg(prp);
+                }
+                None => {
+                    *self = KbgpRecordingState::Idle;
+                    prepare_dlg(prp);
+                }
+            },
+        }
+    }
+
+    pub(crate) fn prepare_pending_input(
+        &mut self,
+        time: f64,
+        prp: &mut KbgpPreparePendingInput,
+        prepare_dlg: impl FnOnce(&mut KbgpPreparePendingInput),
+    ) {
+        match self {
+            KbgpRecordingState::Idle | KbgpRecordingState::Failed(_) => prepare_dlg(prp),
+            KbgpRecordingState::Recording(recorder) => {
+                prepare_dlg(prp);
+                recorder.record_pending_input(time, prp);
+            }
+            KbgpRecordingState::Replaying(replayer) => match replayer.frames.pop_front() {
+                Some(KbgpRecordedFrame {
+                    input: KbgpRecordedInput::PendingInput { inputs, axes },
+                    ..
+                }) => {
+                    replayer.replayed_frames += 1;
+                    replayer.replay_pending_input(inputs, axes, prp);
+                }
+                Some(KbgpRecordedFrame {
+                    input: KbgpRecordedInput::Navigation(_),
+                    ..
+                }) => {
+                    *self = KbgpRecordingState::Failed(KbgpReplayError::ExpectedNavigation {
+                        frame: replayer.replayed_frames,
+                    });
+                    prepare_dlg(prp);
+                }
+                None => {
+                    *self = KbgpRecordingState::Idle;
+                    prepare_dlg(prp);
+                }
+            },
+        }
+    }
+}
+
+/// Start recording the input KBGP receives in [`kbgp_prepare`](crate::kbgp_prepare).
+///
+/// Replaces any recording or replay already in progress.
+///
+/// ```no_run
+/// # use bevy_egui_kbgp::egui;
+/// # use bevy_egui_kbgp::prelude::*;
+/// # use bevy_egui_kbgp::{kbgp_start_recording, kbgp_stop_recording};
+/// # use bevy_egui_kbgp::{KbgpRecordedUserActions, KbgpRecorder};
+/// # #[derive(Clone, PartialEq)]
+/// # enum UserAction { Exit }
+/// # fn ui(ctx: &egui::Context) {
+/// if ctx.input(|input| input.key_pressed(egui::Key::F9)) {
+///     kbgp_start_recording(
+///         ctx,
+///         KbgpRecorder::new(
+///             KbgpRecordedUserActions::default().with_user_action("exit", UserAction::Exit),
+///         ),
+///     );
+/// }
+/// if ctx.input(|input| input.key_pressed(egui::Key::F10)) {
+///     if let Some(recording) = kbgp_stop_recording(ctx) {
+///         recording.save("menu-flow.ron").unwrap();
+///     }
+/// }
+/// # }
+/// ```
+pub fn kbgp_start_recording(egui_ctx: &egui::Context, recorder: KbgpRecorder) {
+    kbgp_get(egui_ctx).lock().recording = KbgpRecordingState::Recording(recorder);
+}
+
+/// Stop the recording started with [`kbgp_start_recording`].
+///
+/// Returns `None` if there was no recording in progress.
+pub fn kbgp_stop_recording(egui_ctx: &egui::Context) -> Option<KbgpRecording> {
+    let kbgp = kbgp_get(egui_ctx);
+    let mut kbgp = kbgp.lock();
+    match std::mem::take(&mut kbgp.recording) {
+        KbgpRecordingState::Recording(recorder) => Some(recorder.recording),
+        other => {
+            kbgp.recording = other;
+            None
+        }
+    }
+}
+
+/// Replace the input KBGP receives in [`kbgp_prepare`](crate::kbgp_prepare) with a recording.
+///
+/// Replaces any recording or replay already in progress.
+pub fn kbgp_start_replay(egui_ctx: &egui::Context, replayer: KbgpReplayer) {
+    kbgp_get(egui_ctx).lock().recording = KbgpRecordingState::Replaying(replayer);
+}
+
+/// Take the reason the last replay started with [`kbgp_start_replay`] stopped early, if it did.
+///
+/// Returns `None` while the replay is running, and if it replayed all the frames.
+pub fn kbgp_take_replay_error(egui_ctx: &egui::Context) -> Option<KbgpReplayError> {
+    let kbgp = kbgp_get(egui_ctx);
+    let mut kbgp = kbgp.lock();
+    let KbgpRecordingState::Failed(error) = kbgp.recording else {
+        return None;
+    };
+    kbgp.recording = KbgpRecordingState::Idle;
+    Some(error)
+}
+
+/// Check if a replay started with [`kbgp_start_replay`] is still running.
+pub fn kbgp_is_replaying(egui_ctx: &egui::Context) -> bool {
+    matches!(
+        kbgp_get(egui_ctx).lock().recording,
+        KbgpRecordingState::Replaying(_)
+    )
+}
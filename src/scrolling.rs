@@ -0,0 +1,165 @@
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+
+use crate::egui;
+
+/// Mapping of gamepad axes to scrolling, for panels (like credits or patch notes) that have no
+/// focusable widgets to navigate through.
+///
+/// Scrolls the `ScrollArea` under the mouse pointer. If the pointer is not over the UI (e.g. when
+/// playing with a gamepad), it scrolls the innermost `ScrollArea` that contains the focused widget.
+/// If the focused widget is not in a `ScrollArea`, it scrolls the first `ScrollArea` that finishes
+/// drawing - which, for nested `ScrollArea`s, is the innermost one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KbgpScrollBinding {
+    /// The axis for scrolling up and down. Pushing it up scrolls up.
+    pub vertical: Option<GamepadAxis>,
+    /// The axis for scrolling left and right. Pushing it right scrolls right.
+    pub horizontal: Option<GamepadAxis>,
+    /// Axis values below this are ignored.
+    ///
+    /// Default: 0.15.
+    pub dead_zone: f32,
+    /// The scrolling speed, in points per second, when the axis is fully tilted.
+    ///
+    /// Default: 1000.
+    pub max_speed: f32,
+    /// The shape of the speed curve. The speed is proportional to the axis value (after removing
+    /// the dead zone) raised to this power, so values above 1 allow finer control when the axis is
+    /// slightly tilted.
+    ///
+    /// Default: 2.
+    pub exponent: f32,
+}
+
+impl Default for KbgpScrollBinding {
+    /// No axes are bound. Use [`KbgpScrollBinding::right_stick`] for the default mapping.
+    fn default() -> Self {
+        Self {
+            vertical: None,
+            horizontal: None,
+            dead_zone: 0.15,
+            max_speed: 1000.0,
+            exponent: 2.0,
+        }
+    }
+}
+
+impl KbgpScrollBinding {
+    /// Scroll with the right stick.
+    ///
+    /// [`KbgpNavBindings::default`](crate::KbgpNavBindings::default) already contains this
+    /// mapping.
+    pub fn right_stick() -> Self {
+        Self {
+            vertical: Some(GamepadAxis::RightStickY),
+            horizontal: Some(GamepadAxis::RightStickX),
+            ..Default::default()
+        }
+    }
+
+    /// The scrolling speed, in points per second, for an axis value.
+    pub fn speed(&self, axis_value: f32) -> f32 {
+        curved_speed(axis_value, self.dead_zone, self.max_speed, self.exponent)
+    }
+
+    /// The scrolling velocity from a gamepad, in egui's scroll direction (positive is up/left).
+    pub(crate) fn velocity(&self, gamepad: &Gamepad) -> egui::Vec2 {
+        let axis_speed = |axis: Option<GamepadAxis>| {
+            axis.and_then(|axis| gamepad.get(axis))
+                .map_or(0.0, |value| self.speed(value))
+        };
+        egui::vec2(-axis_speed(self.horizontal), axis_speed(self.vertical))
+    }
+}
+
+/// Apply a dead zone and a speed curve to an axis value.
+pub(crate) fn curved_speed(axis_value: f32, dead_zone: f32, max_speed: f32, exponent: f32) -> f32 {
+    let magnitude = axis_value.abs();
+    if magnitude <= dead_zone {
+        return 0.0;
+    }
+    let normalized = ((magnitude - dead_zone) / (1.0 - dead_zone)).min(1.0);
+    axis_value.signum() * normalized.powf(exponent) * max_speed
+}
+
+/// Scroll the innermost `ScrollArea` that contains the focused widget, or - if the focused widget is
+/// not in a `ScrollArea` - the first one that finished drawing in the last frame.
+pub(crate) fn scroll_focused_scroll_area(
+    egui_ctx: &egui::Context,
+    focused: Option<egui::Id>,
+    delta: egui::Vec2,
+) {
+    let state_id = egui_ctx.viewport(|viewport| {
+        let prev_pass = &viewport.prev_pass;
+        let layers = prev_pass.widgets.layers().collect::<HashMap<_, _>>();
+        // A `ScrollArea` whose content does not fit registers a widget over its viewport (for
+        // dragging the content) before drawing the content, and its scroll bars after it - all with
+        // IDs derived from the ID of its state.
+        let scroll_areas = prev_pass
+            .used_ids
+            .keys()
+            .filter_map(|state_id| {
+                let area = prev_pass.widgets.get(state_id.with("area"))?;
+                let widgets = layers.get(&area.layer_id)?;
+                let start = widgets.iter().position(|widget| widget.id == area.id)? + 1;
+                let end = widgets[start..]
+                    .iter()
+                    .position(|widget| {
+                        widget.id == state_id.with(0usize) || widget.id == state_id.with(1usize)
+                    })
+                    .map_or(widgets.len(), |len| start + len);
+                Some((*state_id, area.layer_id, start..end))
+            })
+            .collect::<Vec<_>>();
+        let containing_focused = focused
+            .and_then(|focused| {
+                let layer_id = prev_pass.widgets.get(focused)?.layer_id;
+                let index = layers
+                    .get(&layer_id)?
+                    .iter()
+                    .position(|widget| widget.id == focused)?;
+                Some((layer_id, index))
+            })
+            .and_then(|(layer_id, index)| {
+                scroll_areas
+                    .iter()
+                    .filter(|(_, area_layer_id, content)| {
+                        *area_layer_id == layer_id && content.contains(&index)
+                    })
+                    .max_by_key(|(_, _, content)| content.start)
+            });
+        let (state_id, ..) = containing_focused.or_else(|| {
+            scroll_areas
+                .iter()
+                .min_by_key(|(_, _, content)| content.end)
+        })?;
+        Some(*state_id)
+    });
+    let Some((state_id, mut state)) = state_id.and_then(|state_id| {
+        Some((
+            state_id,
+            egui::scroll_area::State::load(egui_ctx, state_id)?,
+        ))
+    }) else {
+        return;
+    };
+    // The `ScrollArea` only clamps the offset to its content after drawing it, so once it clamped
+    // the offset we requested, stop pushing past that end - or the content will be drawn past it.
+    let requested_id = state_id.with("kbgp_requested_offset");
+    let prev_requested = egui_ctx.data(|data| data.get_temp::<egui::Vec2>(requested_id));
+    let mut requested = state.offset;
+    for d in 0..2 {
+        match prev_requested {
+            Some(prev_requested) if delta[d] < 0.0 && state.offset[d] < prev_requested[d] => {
+                requested[d] = prev_requested[d];
+            }
+            _ => {
+                state.offset[d] = (state.offset[d] - delta[d]).max(0.0);
+                requested[d] = state.offset[d];
+            }
+        }
+    }
+    state.store(egui_ctx, state_id);
+    egui_ctx.data_mut(|data| data.insert_temp(requested_id, requested));
+}
@@ -0,0 +1,127 @@
+//! Integration with Bevy states.
+//!
+//! Requires the `states` feature.
+
+use std::sync::Arc;
+
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass};
+
+use crate::egui;
+use crate::{kbgp_get, kbgp_system_default_input, KbgpEguiUiCtxExt, KbgpState, KbgpSystems};
+
+type SetFocusLabel = Arc<dyn Fn(&egui::Context) + Send + Sync>;
+
+/// Clears KBGP's input on every transition of the state `S`.
+///
+/// Without this plugin, [`kbgp_clear_input`](crate::KbgpEguiUiCtxExt::kbgp_clear_input) must be
+/// called when an egui widget triggers a state transition - otherwise the same player input that
+/// triggered the transition will be applied again to the GUI in the new state. With it, the input
+/// is cleared after the frame where the widget was activated, but before the UI of the new state is
+/// drawn - in [`KbgpSystems::Prepare`], so UI systems should be ordered after that set.
+///
+/// The plugin can also focus on a widget when entering a state, by setting a focus label for the
+/// state:
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use bevy_egui_kbgp::prelude::*;
+/// use bevy_egui_kbgp::KbgpStatesPlugin;
+///
+/// #[derive(States, Debug, Clone, PartialEq, Eq, Hash, Default)]
+/// enum MenuState {
+///     #[default]
+///     Main,
+///     Options,
+/// }
+///
+/// #[derive(Clone, PartialEq)]
+/// enum FocusLabel {
+///     Play,
+///     Back,
+/// }
+///
+/// App::new()
+///     .add_plugins(KbgpPlugin)
+///     .init_state::<MenuState>()
+///     .add_plugins(
+///         KbgpStatesPlugin::<MenuState>::default()
+///             .with_focus_label(MenuState::Main, FocusLabel::Play)
+///             .with_focus_label(MenuState::Options, FocusLabel::Back),
+///     );
+/// ```
+pub struct KbgpStatesPlugin<S: States> {
+    focus_labels: HashMap<S, SetFocusLabel>,
+}
+
+impl<S: States> Default for KbgpStatesPlugin<S> {
+    fn default() -> Self {
+        Self {
+            focus_labels: Default::default(),
+        }
+    }
+}
+
+impl<S: States> KbgpStatesPlugin<S> {
+    /// Focus on the widget marked with
+    /// [`kbgp_focus_label`](crate::KbgpEguiResponseExt::kbgp_focus_label) when entering `state`.
+    pub fn with_focus_label<T: 'static + Clone + Send + Sync>(
+        mut self,
+        state: S,
+        label: T,
+    ) -> Self {
+        self.focus_labels.insert(
+            state,
+            Arc::new(move |egui_ctx: &egui::Context| {
+                let kbgp = kbgp_get(egui_ctx);
+                let mut kbgp = kbgp.lock();
+                if let KbgpState::Navigation(state) = &mut kbgp.state {
+                    // KBGP already prepared this frame, so the label will be used by the UI of
+                    // the new state that is about to be drawn.
+                    state.focus_label = Some(Box::new(label.clone()));
+                }
+            }),
+        );
+        self
+    }
+}
+
+impl<S: States> Plugin for KbgpStatesPlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(KbgpStateFocusLabels::<S> {
+            focus_labels: self.focus_labels.clone(),
+        });
+        app.add_systems(
+            EguiPrimaryContextPass,
+            kbgp_clear_input_on_state_transition::<S>
+                .run_if(on_event::<StateTransitionEvent<S>>)
+                .in_set(KbgpSystems::Prepare)
+                .after(kbgp_system_default_input),
+        );
+    }
+}
+
+#[derive(Resource)]
+struct KbgpStateFocusLabels<S: States> {
+    focus_labels: HashMap<S, SetFocusLabel>,
+}
+
+fn kbgp_clear_input_on_state_transition<S: States>(
+    mut egui_context: EguiContexts,
+    mut transitions: EventReader<StateTransitionEvent<S>>,
+    focus_labels: Res<KbgpStateFocusLabels<S>>,
+) -> Result {
+    let egui_ctx = egui_context.ctx_mut()?;
+    for transition in transitions.read() {
+        egui_ctx.kbgp_clear_input();
+        let set_focus_label = transition
+            .entered
+            .as_ref()
+            .and_then(|state| focus_labels.focus_labels.get(state));
+        if let Some(set_focus_label) = set_focus_label {
+            set_focus_label(egui_ctx);
+        }
+    }
+    Ok(())
+}
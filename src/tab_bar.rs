@@ -0,0 +1,89 @@
+use crate::egui;
+use crate::{kbgp_get, KbgpEguiResponseExt, KbgpState};
+
+pub(crate) fn show_tab_bar<T: Clone + PartialEq>(
+    ui: &mut egui::Ui,
+    selected: &mut T,
+    tabs: impl IntoIterator<Item = (T, impl Into<egui::WidgetText>)>,
+) -> egui::Response {
+    let tab_step = {
+        let kbgp = kbgp_get(ui.ctx());
+        let kbgp = kbgp.lock();
+        match &kbgp.state {
+            KbgpState::Navigation(state) => state.tab_step,
+            KbgpState::PendingInput(_) => 0,
+        }
+    };
+    // The page of the selected tab is drawn in the same `Ui` as the tab bar, so widgets outside it
+    // (e.g. in a side panel) do not belong to the tab.
+    let page_layer = ui.layer_id();
+    let page_rect = ui.max_rect();
+    let focused_in_page = ui
+        .ctx()
+        .memory(|memory| memory.focused())
+        .filter(|focused| {
+            ui.ctx().viewport(|viewport| {
+                viewport
+                    .prev_pass
+                    .widgets
+                    .get(*focused)
+                    .is_some_and(|widget| {
+                        widget.layer_id == page_layer && page_rect.contains(widget.rect.center())
+                    })
+            })
+        });
+
+    let inner_response = ui.horizontal(|ui| {
+        let bar_id = ui.id().with("kbgp_tab_bar");
+        let tabs = tabs.into_iter().collect::<Vec<_>>();
+        // The last widget focused in each tab, by the tab's index.
+        let mut last_focus = ui
+            .ctx()
+            .data(|data| data.get_temp::<Vec<Option<egui::Id>>>(bar_id))
+            .unwrap_or_default();
+        last_focus.resize(tabs.len(), None);
+
+        let prev_index = tabs.iter().position(|(value, _)| value == selected);
+        let mut switched_to = None;
+        if tab_step != 0 && !tabs.is_empty() {
+            let index = match prev_index {
+                Some(index) => (index as isize + tab_step).rem_euclid(tabs.len() as isize) as usize,
+                None => 0,
+            };
+            *selected = tabs[index].0.clone();
+            switched_to = Some(index);
+        }
+
+        let mut changed = switched_to.is_some();
+        let mut tab_ids = Vec::with_capacity(tabs.len());
+        for (value, text) in tabs {
+            let response = ui
+                .selectable_label(*selected == value, text)
+                .kbgp_navigation();
+            if response.clicked() && *selected != value {
+                *selected = value;
+                changed = true;
+            }
+            tab_ids.push(response.id);
+        }
+
+        if let (Some(prev_index), Some(focused)) = (prev_index, focused_in_page) {
+            if !tab_ids.contains(&focused) {
+                last_focus[prev_index] = Some(focused);
+            }
+        }
+        if let Some(index) = switched_to {
+            let focus_on = last_focus[index].unwrap_or(tab_ids[index]);
+            ui.ctx().memory_mut(|memory| memory.request_focus(focus_on));
+        }
+        ui.ctx()
+            .data_mut(|data| data.insert_temp(bar_id, last_focus));
+        changed
+    });
+
+    let mut response = inner_response.response;
+    if inner_response.inner {
+        response.mark_changed();
+    }
+    response
+}
@@ -0,0 +1,247 @@
+//! Headless harness for testing KBGP navigation without a Bevy app.
+//!
+//! Requires the `testing` feature.
+//!
+//! ```
+//! use bevy_egui_kbgp::prelude::*;
+//! use bevy_egui_kbgp::testing::KbgpTestHarness;
+//!
+//! let mut harness = KbgpTestHarness::new();
+//! let menu = |ui: &mut bevy_egui_kbgp::egui::Ui| {
+//!     ui.button("Play")
+//!         .kbgp_navigation()
+//!         .kbgp_initial_focus()
+//!         .kbgp_focus_label("play");
+//!     ui.button("Options")
+//!         .kbgp_navigation()
+//!         .kbgp_focus_label("options");
+//! };
+//! harness.run_frames(2, menu);
+//! harness.assert_focused("play");
+//! harness.press(&[KbgpNavCommand::NavigateDown], menu);
+//! harness.assert_focused("options");
+//! ```
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use crate::egui;
+use crate::{
+    kbgp_prepare, kbgp_take_virtual_cursor_events, kbgp_validate_focus, KbgpAxisInput,
+    KbgpDisabledWidgetPolicy, KbgpInput, KbgpInputSourceProviders, KbgpNavCommand, KbgpPrepare,
+};
+
+/// Drives an `egui::Context` with KBGP, frame by frame, without Bevy.
+pub struct KbgpTestHarness {
+    ctx: egui::Context,
+    time: f64,
+    /// The simulated duration of each frame, in seconds.
+    ///
+    /// Default: 1/60 seconds.
+    pub frame_secs: f64,
+    /// The simulated screen size.
+    ///
+    /// Default: 800x600.
+    pub screen_size: egui::Vec2,
+    /// How to treat disabled widgets. See
+    /// [`KbgpPrepareNavigation::disabled_widgets`](crate::KbgpPrepareNavigation::disabled_widgets).
+    ///
+    /// Default: [`KbgpDisabledWidgetPolicy::Focusable`].
+    pub disabled_widgets: KbgpDisabledWidgetPolicy,
+    /// Whether or not to navigate between all the interactive widgets. See
+    /// [`KbgpPrepareNavigation::auto_navigation`](crate::KbgpPrepareNavigation::auto_navigation).
+    ///
+    /// Default: `false`.
+    pub auto_navigation: bool,
+    /// Scroll velocity, in points per second, applied in every frame - like
+    /// [`KbgpPrepareNavigation::scroll_gamepad_by_binding`](crate::KbgpPrepareNavigation::scroll_gamepad_by_binding)
+    /// does with a gamepad's axes. The mouse pointer is never over the UI.
+    ///
+    /// Default: zero.
+    pub scroll_velocity: egui::Vec2,
+    /// Values of analog axes, reported in every frame while KBGP waits for the player to enter
+    /// input - like
+    /// [`KbgpPreparePendingInput::accept_axis_input`](crate::KbgpPreparePendingInput::accept_axis_input)
+    /// does.
+    ///
+    /// Default: empty.
+    pub axes: Vec<(KbgpAxisInput, f32)>,
+    /// Custom input sources, fed in every frame after the commands and the inputs - like
+    /// [`KbgpPlugin`](crate::KbgpPlugin) does.
+    ///
+    /// Default: none.
+    pub input_source_providers: KbgpInputSourceProviders,
+    /// Raw input events for the next frame (e.g. from the virtual cursor).
+    events: Vec<egui::Event>,
+    /// The focus labels given to widgets in the last frame.
+    focus_labels: FocusLabels,
+}
+
+impl Default for KbgpTestHarness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KbgpTestHarness {
+    /// Create a harness with a fresh `egui::Context`.
+    pub fn new() -> Self {
+        Self::with_ctx(egui::Context::default())
+    }
+
+    /// Create a harness that drives an existing `egui::Context` - e.g. the one of a Bevy app, for
+    /// testing systems that use it.
+    pub fn with_ctx(ctx: egui::Context) -> Self {
+        Self {
+            ctx,
+            time: 0.0,
+            frame_secs: 1.0 / 60.0,
+            screen_size: egui::vec2(800.0, 600.0),
+            disabled_widgets: KbgpDisabledWidgetPolicy::Focusable,
+            auto_navigation: false,
+            scroll_velocity: egui::Vec2::ZERO,
+            axes: Vec::new(),
+            input_source_providers: Default::default(),
+            events: Vec::new(),
+            focus_labels: Vec::new(),
+        }
+    }
+
+    /// The egui context driven by the harness.
+    pub fn ctx(&self) -> &egui::Context {
+        &self.ctx
+    }
+
+    /// The simulated time of the next frame, in seconds.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// Run a single frame, with the given commands and inputs held during it.
+    ///
+    /// `commands` are used while KBGP is in navigation mode, and `inputs` are used while it waits
+    /// for the player to enter input (e.g. after activating a
+    /// [`kbgp_pending_input`](crate::KbgpEguiResponseExt::kbgp_pending_input) button). The UI is
+    /// drawn inside an `egui::CentralPanel`.
+    ///
+    /// Note that, just like real keys, holding the same command in consecutive frames will not
+    /// repeat it until the initial repeat delay passes.
+    pub fn run_frame(
+        &mut self,
+        commands: &[KbgpNavCommand],
+        inputs: &[KbgpInput],
+        add_contents: impl FnOnce(&mut egui::Ui),
+    ) {
+        self.ctx.begin_pass(egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(
+                egui::Pos2::ZERO,
+                self.screen_size,
+            )),
+            time: Some(self.time),
+            events: std::mem::take(&mut self.events),
+            ..Default::default()
+        });
+        kbgp_prepare(&self.ctx, |prp| match prp {
+            KbgpPrepare::Navigation(prp) => {
+                prp.disabled_widgets = self.disabled_widgets;
+                prp.auto_navigation = self.auto_navigation;
+                prp.scroll_velocity += self.scroll_velocity;
+                for command in commands {
+                    prp.apply_action(command);
+                }
+                self.input_source_providers.navigation(prp);
+            }
+            KbgpPrepare::PendingInput(prp) => {
+                prp.accept_inputs(inputs.iter().cloned());
+                for (axis, value) in &self.axes {
+                    prp.accept_axis_input(axis.clone(), *value);
+                }
+                self.input_source_providers.pending_input(prp);
+            }
+        });
+        FRAME_FOCUS_LABELS.set(Some(Vec::new()));
+        egui::CentralPanel::default().show(&self.ctx, add_contents);
+        self.focus_labels = FRAME_FOCUS_LABELS.take().unwrap_or_default();
+        let _ = self.ctx.end_pass();
+        kbgp_validate_focus(&self.ctx);
+        self.events = kbgp_take_virtual_cursor_events(&self.ctx);
+        self.time += self.frame_secs;
+    }
+
+    /// Run frames without any command or input.
+    pub fn run_frames(&mut self, count: usize, mut add_contents: impl FnMut(&mut egui::Ui)) {
+        for _ in 0..count {
+            self.run_frame(&[], &[], &mut add_contents);
+        }
+    }
+
+    /// Run a frame where the commands are held, followed by a frame where they are released.
+    pub fn press(
+        &mut self,
+        commands: &[KbgpNavCommand],
+        mut add_contents: impl FnMut(&mut egui::Ui),
+    ) {
+        self.run_frame(commands, &[], &mut add_contents);
+        self.run_frame(&[], &[], &mut add_contents);
+    }
+
+    /// Run a frame where the inputs are held, followed by a frame where they are released.
+    pub fn press_input(
+        &mut self,
+        inputs: &[KbgpInput],
+        mut add_contents: impl FnMut(&mut egui::Ui),
+    ) {
+        self.run_frame(&[], inputs, &mut add_contents);
+        self.run_frame(&[], &[], &mut add_contents);
+    }
+
+    /// The widget that has the focus.
+    pub fn focused(&self) -> Option<egui::Id> {
+        self.ctx.memory(|memory| memory.focused())
+    }
+
+    /// The ID of the widget that was given the
+    /// [`kbgp_focus_label`](crate::KbgpEguiResponseExt::kbgp_focus_label) in the last frame.
+    pub fn id_of<T: 'static + PartialEq>(&self, label: &T) -> Option<egui::Id> {
+        self.focus_labels.iter().find_map(|(id, widget_label)| {
+            (widget_label.downcast_ref::<T>() == Some(label)).then_some(*id)
+        })
+    }
+
+    /// Panic if the given widget does not have the focus.
+    ///
+    /// The widget is identified either by an `egui::Id` or by a value it was given with
+    /// [`kbgp_focus_label`](crate::KbgpEguiResponseExt::kbgp_focus_label) in the last frame.
+    #[track_caller]
+    pub fn assert_focused<T: 'static + PartialEq + Debug>(&self, widget: T) {
+        let focused = self.focused();
+        let is_focused = if let Some(id) = (&widget as &dyn Any).downcast_ref::<egui::Id>() {
+            focused == Some(*id)
+        } else {
+            focused.is_some() && focused == self.id_of(&widget)
+        };
+        assert!(
+            is_focused,
+            "expected {widget:?} to be focused, but the focus is on {focused:?}"
+        );
+    }
+}
+
+type FocusLabels = Vec<(egui::Id, Arc<dyn Any + Send + Sync>)>;
+
+thread_local! {
+    /// The focus labels given to widgets in the frame the harness is running, if it is running one.
+    static FRAME_FOCUS_LABELS: RefCell<Option<FocusLabels>> =
+        const { RefCell::new(None) };
+}
+
+/// Let the harness know which focus label a widget was given.
+pub(crate) fn record_focus_label(id: egui::Id, label: Arc<dyn Any + Send + Sync>) {
+    FRAME_FOCUS_LABELS.with_borrow_mut(|labels| {
+        if let Some(labels) = labels {
+            labels.push((id, label));
+        }
+    });
+}
@@ -0,0 +1,173 @@
+//! Keyboard and gamepad navigation for bevy_ui nodes.
+//!
+//! Requires the `bevy_ui` feature.
+
+use std::any::Any;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+
+use crate::egui;
+use crate::navigation::{KbgpNodeNavigation, KbgpNodeNavigationOutcome, KbgpPrepareNavigation};
+use crate::KbgpSettings;
+
+/// Navigate between bevy_ui nodes with the same bindings as the egui navigation.
+///
+/// Mark the navigable nodes with [`KbgpNode`], and insert [`KbgpFocused`] on the one that should
+/// start with the focus. The plugin only acts while some entity has [`KbgpFocused`] - remove it
+/// when the player switches to an egui menu, so that the same input will not navigate both.
+///
+/// Navigation uses [`KbgpSettings::bindings`](crate::KbgpSettings::bindings). Of the
+/// [`KbgpNavCommand`](crate::KbgpNavCommand)s, only navigation, `Click` and user actions have
+/// effect on bevy_ui nodes. Activating the focused node triggers [`KbgpNodeActivated`] on it:
+///
+/// ```no_run
+/// use bevy::prelude::*;
+/// use bevy_egui_kbgp::prelude::*;
+/// use bevy_egui_kbgp::{KbgpFocused, KbgpNode, KbgpNodeActivated, KbgpUiPlugin};
+///
+/// fn setup(mut commands: Commands) {
+///     commands
+///         .spawn((Node::default(), KbgpNode, KbgpFocused))
+///         .observe(|trigger: Trigger<KbgpNodeActivated>, mut commands: Commands| {
+///             if !trigger.released {
+///                 commands.entity(trigger.target()).despawn();
+///             }
+///         });
+///     commands.spawn((Node::default(), KbgpNode));
+/// }
+///
+/// App::new()
+///     .add_plugins(KbgpPlugin)
+///     .add_plugins(KbgpUiPlugin)
+///     .add_systems(Startup, setup);
+/// ```
+pub struct KbgpUiPlugin;
+
+impl Plugin for KbgpUiPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<KbgpSettings>();
+        app.add_systems(
+            PreUpdate,
+            kbgp_ui_navigation_system.after(bevy::input::InputSystem),
+        );
+    }
+}
+
+/// A bevy_ui node that can get the focus with keyboard and gamepad navigation.
+///
+/// The node's rect is taken from its `ComputedNode` and `GlobalTransform`. Hidden nodes and
+/// nodes without size are skipped.
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct KbgpNode;
+
+/// The bevy_ui node that has the focus. [`KbgpUiPlugin`] moves it between the [`KbgpNode`]s.
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct KbgpFocused;
+
+/// Triggered on the focused [`KbgpNode`] when the player activates it.
+///
+/// Like egui widgets, which have both [`kbgp_activated`](crate::KbgpEguiResponseExt::kbgp_activated)
+/// and [`kbgp_activate_released`](crate::KbgpEguiResponseExt::kbgp_activate_released), a node is
+/// triggered twice - once when the key/button is pressed, and once when it is released. The
+/// release is only triggered if the node kept the focus while the key/button was held.
+#[derive(Event)]
+pub struct KbgpNodeActivated {
+    /// Whether this is the release of the key/button rather than its press.
+    pub released: bool,
+    user_action: Option<Arc<dyn Any + Send + Sync>>,
+}
+
+impl KbgpNodeActivated {
+    /// Whether the node was activated with [`KbgpNavCommand::Click`](crate::KbgpNavCommand::Click)
+    /// rather than with a user action.
+    pub fn is_click(&self) -> bool {
+        self.user_action.is_none()
+    }
+
+    /// The user action the node was activated with, if it was activated with a
+    /// [`KbgpNavCommand::user`](crate::KbgpNavCommand::user) of type `T`.
+    pub fn user_action<T: 'static + Clone>(&self) -> Option<T> {
+        self.user_action.as_ref()?.downcast_ref::<T>().cloned()
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn kbgp_ui_navigation_system(
+    mut commands: Commands,
+    settings: Res<KbgpSettings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    time: Res<Time<Real>>,
+    nodes: Query<
+        (
+            Entity,
+            &ComputedNode,
+            &GlobalTransform,
+            &InheritedVisibility,
+        ),
+        With<KbgpNode>,
+    >,
+    focused: Query<Entity, With<KbgpFocused>>,
+    mut navigation: Local<KbgpNodeNavigation<Entity>>,
+) {
+    let Some(current_focus) = focused.iter().next() else {
+        // Whatever was held should not affect the nodes once something gets the focus again.
+        *navigation = Default::default();
+        return;
+    };
+
+    let mut handle = KbgpPrepareNavigation::for_nodes();
+    if settings.allow_keyboard {
+        handle.navigate_keyboard_by_binding(
+            &keys,
+            &settings.bindings.keyboard,
+            !settings.disable_default_activation,
+        );
+    }
+    if settings.allow_gamepads {
+        for gamepad in gamepads.iter() {
+            handle.navigate_gamepad_by_binding(gamepad, &settings.bindings.gamepad_buttons);
+        }
+    }
+
+    let nodes = nodes
+        .iter()
+        .filter(|(_, computed_node, _, visibility)| visibility.get() && !computed_node.is_empty())
+        .map(|(entity, computed_node, global_transform, _)| {
+            let center = global_transform.translation().truncate();
+            let size = computed_node.size();
+            (
+                entity,
+                egui::Rect::from_center_size(
+                    egui::pos2(center.x, center.y),
+                    egui::vec2(size.x, size.y),
+                ),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    for outcome in navigation.apply(handle, time.elapsed_secs_f64(), Some(current_focus), &nodes) {
+        match outcome {
+            KbgpNodeNavigationOutcome::MoveFocus(target) => {
+                for entity in focused.iter() {
+                    commands.entity(entity).remove::<KbgpFocused>();
+                }
+                commands.entity(target).insert(KbgpFocused);
+            }
+            KbgpNodeNavigationOutcome::Activate {
+                node,
+                released,
+                user_action,
+            } => {
+                commands.trigger_targets(
+                    KbgpNodeActivated {
+                        released,
+                        user_action,
+                    },
+                    node,
+                );
+            }
+        }
+    }
+}
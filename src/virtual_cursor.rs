@@ -0,0 +1,165 @@
+use bevy::prelude::*;
+
+use crate::egui;
+use crate::kbgp_get;
+use crate::scrolling::curved_speed;
+
+/// Mapping of gamepad axes to the virtual cursor - an on-screen pointer for the parts of the UI
+/// that are not made of buttons (maps, canvases, plots).
+///
+/// The virtual cursor is active while it is toggled on with
+/// [`KbgpNavCommand::ToggleVirtualCursor`](crate::KbgpNavCommand::ToggleVirtualCursor), and
+/// while a widget marked with
+/// [`kbgp_virtual_cursor`](crate::KbgpEguiResponseExt::kbgp_virtual_cursor) has the focus. While
+/// it is active, [`KbgpNavCommand::Click`](crate::KbgpNavCommand::Click) presses the pointer's
+/// primary button instead of activating the focused widget.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KbgpVirtualCursorBinding {
+    /// The axis for moving the cursor left and right. Pushing it right moves right.
+    pub horizontal: Option<GamepadAxis>,
+    /// The axis for moving the cursor up and down. Pushing it up moves up.
+    pub vertical: Option<GamepadAxis>,
+    /// Axis values below this are ignored.
+    ///
+    /// Default: 0.15.
+    pub dead_zone: f32,
+    /// The cursor speed, in points per second, when the axis is fully tilted.
+    ///
+    /// Default: 800.
+    pub max_speed: f32,
+    /// The shape of the speed curve. See [`KbgpScrollBinding::exponent`](crate::KbgpScrollBinding::exponent).
+    ///
+    /// Default: 2.
+    pub exponent: f32,
+}
+
+impl Default for KbgpVirtualCursorBinding {
+    /// No axes are bound. Use [`KbgpVirtualCursorBinding::left_stick`] for the default mapping.
+    fn default() -> Self {
+        Self {
+            horizontal: None,
+            vertical: None,
+            dead_zone: 0.15,
+            max_speed: 800.0,
+            exponent: 2.0,
+        }
+    }
+}
+
+impl KbgpVirtualCursorBinding {
+    /// Move the virtual cursor with the left stick.
+    ///
+    /// [`KbgpNavBindings::default`](crate::KbgpNavBindings::default) already contains this
+    /// mapping.
+    pub fn left_stick() -> Self {
+        Self {
+            horizontal: Some(GamepadAxis::LeftStickX),
+            vertical: Some(GamepadAxis::LeftStickY),
+            ..Default::default()
+        }
+    }
+
+    /// The cursor speed, in points per second, for an axis value.
+    pub fn speed(&self, axis_value: f32) -> f32 {
+        curved_speed(axis_value, self.dead_zone, self.max_speed, self.exponent)
+    }
+
+    /// The cursor velocity from a gamepad, in screen coordinates (positive is down/right).
+    pub(crate) fn velocity(&self, gamepad: &Gamepad) -> egui::Vec2 {
+        let axis_speed = |axis: Option<GamepadAxis>| {
+            axis.and_then(|axis| gamepad.get(axis))
+                .map_or(0.0, |value| self.speed(value))
+        };
+        egui::vec2(axis_speed(self.horizontal), -axis_speed(self.vertical))
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct VirtualCursorState {
+    /// Set by [`KbgpNavCommand::ToggleVirtualCursor`](crate::KbgpNavCommand::ToggleVirtualCursor).
+    pub(crate) toggled_on: bool,
+    /// The position of the cursor while it is active.
+    pub(crate) pos: Option<egui::Pos2>,
+    pub(crate) button_down: bool,
+    /// Pointer events for egui's raw input of the next frame.
+    pub(crate) pending_events: Vec<egui::Event>,
+}
+
+impl VirtualCursorState {
+    /// Move the cursor, keeping it inside `bounds`. If the cursor was not active, it is activated
+    /// at `start`.
+    pub(crate) fn update(
+        &mut self,
+        bounds: egui::Rect,
+        start: Option<egui::Pos2>,
+        delta: egui::Vec2,
+        click_held: bool,
+    ) {
+        let prev_pos = self.pos;
+        let pos = match prev_pos.or(start) {
+            Some(pos) if bounds.contains(pos) => pos,
+            _ => bounds.center(),
+        };
+        let pos = bounds.clamp(pos + delta);
+        self.pos = Some(pos);
+        if prev_pos != Some(pos) {
+            self.pending_events.push(egui::Event::PointerMoved(pos));
+        }
+        if click_held != self.button_down {
+            self.button_down = click_held;
+            self.pending_events.push(egui::Event::PointerButton {
+                pos,
+                button: egui::PointerButton::Primary,
+                pressed: click_held,
+                modifiers: Default::default(),
+            });
+        }
+    }
+
+    /// Release the pointer button and hide the cursor.
+    pub(crate) fn deactivate(&mut self) {
+        let Some(pos) = self.pos.take() else {
+            return;
+        };
+        if self.button_down {
+            self.button_down = false;
+            self.pending_events.push(egui::Event::PointerButton {
+                pos,
+                button: egui::PointerButton::Primary,
+                pressed: false,
+                modifiers: Default::default(),
+            });
+        }
+        self.pending_events.push(egui::Event::PointerGone);
+    }
+
+    pub(crate) fn paint(&self, egui_ctx: &egui::Context) {
+        let Some(pos) = self.pos else {
+            return;
+        };
+        let painter = egui_ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Tooltip,
+            egui::Id::new("kbgp_virtual_cursor"),
+        ));
+        let visuals = &egui_ctx.style().visuals;
+        let radius = if self.button_down { 4.0 } else { 6.0 };
+        painter.circle(
+            pos,
+            radius,
+            visuals.selection.bg_fill,
+            visuals.selection.stroke,
+        );
+    }
+}
+
+/// Take the pointer events generated by the virtual cursor.
+///
+/// egui only moves its pointer when the events arrive in the raw input, so these events should be
+/// added to the raw input of the next frame. [`KbgpPlugin`](crate::KbgpPlugin) does this by
+/// writing them as `EguiInputEvent`s - this only needs to be called by apps that drive egui
+/// themselves.
+pub fn kbgp_take_virtual_cursor_events(egui_ctx: &egui::Context) -> Vec<egui::Event> {
+    let kbgp = kbgp_get(egui_ctx);
+    let mut kbgp = kbgp.lock();
+    std::mem::take(&mut kbgp.common.virtual_cursor.pending_events)
+}
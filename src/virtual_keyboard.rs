@@ -0,0 +1,279 @@
+use crate::egui;
+use crate::navigation::KbgpNavDirection;
+use crate::{kbgp_get, KbgpEguiUiCtxExt, KbgpState};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum VirtualKey {
+    Char(char),
+    Shift,
+    Space,
+    Backspace,
+    Cancel,
+    Confirm,
+}
+
+impl VirtualKey {
+    fn label(&self, shift: bool) -> String {
+        match self {
+            VirtualKey::Char(c) if shift => c.to_uppercase().to_string(),
+            VirtualKey::Char(c) => c.to_string(),
+            VirtualKey::Shift => "Shift".to_owned(),
+            VirtualKey::Space => "Space".to_owned(),
+            VirtualKey::Backspace => "Backspace".to_owned(),
+            VirtualKey::Cancel => "Cancel".to_owned(),
+            VirtualKey::Confirm => "Done".to_owned(),
+        }
+    }
+}
+
+const CHAR_ROWS: [&str; 4] = ["1234567890", "qwertyuiop", "asdfghjkl'", "zxcvbnm,.-"];
+
+const SPECIAL_ROW: [VirtualKey; 5] = [
+    VirtualKey::Shift,
+    VirtualKey::Space,
+    VirtualKey::Backspace,
+    VirtualKey::Cancel,
+    VirtualKey::Confirm,
+];
+
+fn layout() -> impl Iterator<Item = Vec<VirtualKey>> {
+    CHAR_ROWS
+        .into_iter()
+        .map(|row| row.chars().map(VirtualKey::Char).collect())
+        .chain([SPECIAL_ROW.to_vec()])
+}
+
+fn key_at(row: usize, column: usize) -> VirtualKey {
+    let row = layout()
+        .nth(row)
+        .expect("cursor row is always in the layout");
+    row[column.min(row.len() - 1)]
+}
+
+/// The state of the virtual keyboard while it is open.
+#[derive(Debug)]
+pub(crate) struct VirtualKeyboardState {
+    /// The `TextEdit` the keyboard types into. It keeps the egui focus while the keyboard is open.
+    pub(crate) target: egui::Id,
+    row: usize,
+    column: usize,
+    shift: bool,
+    /// Set the first time the keyboard is drawn, so that cancelling can restore it.
+    original_text: Option<String>,
+    cancelled: bool,
+    /// Keys clicked with the mouse, to be typed in the next frame.
+    pending_keys: Vec<VirtualKey>,
+}
+
+impl VirtualKeyboardState {
+    pub(crate) fn new(target: egui::Id) -> Self {
+        Self {
+            target,
+            row: 0,
+            column: 0,
+            shift: false,
+            original_text: None,
+            cancelled: false,
+            pending_keys: Vec::new(),
+        }
+    }
+
+    pub(crate) fn move_cursor(&mut self, direction: KbgpNavDirection) {
+        let rows_count = CHAR_ROWS.len() + 1;
+        let row_len = |row: usize| layout().nth(row).map_or(0, |row| row.len());
+        // Keep the column in the range of the current row, so that moving horizontally from a
+        // clamped column does not skip keys.
+        self.column = self.column.min(row_len(self.row) - 1);
+        match direction {
+            KbgpNavDirection::Up => {
+                self.row = (self.row + rows_count - 1) % rows_count;
+            }
+            KbgpNavDirection::Down => {
+                self.row = (self.row + 1) % rows_count;
+            }
+            KbgpNavDirection::Left => {
+                let len = row_len(self.row);
+                self.column = (self.column + len - 1) % len;
+            }
+            KbgpNavDirection::Right => {
+                self.column = (self.column + 1) % row_len(self.row);
+            }
+        }
+    }
+
+    pub(crate) fn press_under_cursor(&mut self, egui_ctx: &egui::Context) -> bool {
+        self.press(egui_ctx, key_at(self.row, self.column))
+    }
+
+    pub(crate) fn press_pending(&mut self, egui_ctx: &egui::Context) -> bool {
+        let mut keep_open = true;
+        for key in std::mem::take(&mut self.pending_keys) {
+            keep_open &= self.press(egui_ctx, key);
+        }
+        keep_open
+    }
+
+    /// Returns `false` if the key closes the keyboard.
+    fn press(&mut self, egui_ctx: &egui::Context, key: VirtualKey) -> bool {
+        let event = match key {
+            VirtualKey::Char(_) => egui::Event::Text(key.label(self.shift)),
+            VirtualKey::Space => egui::Event::Text(" ".to_owned()),
+            VirtualKey::Backspace => egui::Event::Key {
+                key: egui::Key::Backspace,
+                physical_key: None,
+                pressed: true,
+                repeat: false,
+                modifiers: Default::default(),
+            },
+            VirtualKey::Shift => {
+                self.shift = !self.shift;
+                return true;
+            }
+            VirtualKey::Cancel => {
+                // Restoring the text requires access to it, so it is done when the keyboard is
+                // drawn.
+                self.cancelled = true;
+                return true;
+            }
+            VirtualKey::Confirm => {
+                return false;
+            }
+        };
+        egui_ctx.input_mut(|input| input.events.push(event));
+        true
+    }
+}
+
+/// User actions that operate the virtual keyboard opened by
+/// [`kbgp_virtual_keyboard`](crate::KbgpEguiResponseExt::kbgp_virtual_keyboard), in addition to
+/// its on-screen keys.
+///
+/// The actions are bound like any other user action - with [`KbgpNavCommand::user`](crate::KbgpNavCommand::user).
+#[derive(Debug, Clone)]
+pub struct KbgpVirtualKeyboardActions<T> {
+    /// Close the keyboard, keeping the text.
+    pub confirm: Option<T>,
+    /// Close the keyboard, restoring the text to what it was before the keyboard was opened.
+    pub cancel: Option<T>,
+    /// Delete the character before the cursor.
+    pub backspace: Option<T>,
+}
+
+impl<T> Default for KbgpVirtualKeyboardActions<T> {
+    fn default() -> Self {
+        Self {
+            confirm: None,
+            cancel: None,
+            backspace: None,
+        }
+    }
+}
+
+pub(crate) fn show_virtual_keyboard<T: 'static + Clone + PartialEq>(
+    response: &egui::Response,
+    text: &mut String,
+    actions: &KbgpVirtualKeyboardActions<T>,
+) {
+    let egui_ctx = &response.ctx;
+    let user_action = egui_ctx.kbgp_user_action::<T>();
+    let kbgp = kbgp_get(egui_ctx);
+    let mut kbgp = kbgp.lock();
+    let KbgpState::Navigation(state) = &mut kbgp.state else {
+        return;
+    };
+    let Some(keyboard) = state
+        .virtual_keyboard
+        .as_mut()
+        .filter(|keyboard| keyboard.target == response.id)
+    else {
+        return;
+    };
+    if keyboard.original_text.is_none() {
+        keyboard.original_text = Some(text.clone());
+    }
+
+    let mut action_handled = true;
+    let mut close = false;
+    match user_action {
+        Some(action) if actions.confirm.as_ref() == Some(&action) => {
+            close = true;
+        }
+        Some(action) if actions.cancel.as_ref() == Some(&action) => {
+            keyboard.cancelled = true;
+        }
+        Some(action) if actions.backspace.as_ref() == Some(&action) => {
+            keyboard.pending_keys.push(VirtualKey::Backspace);
+        }
+        _ => {
+            action_handled = false;
+        }
+    }
+    if keyboard.cancelled {
+        *text = keyboard.original_text.clone().unwrap_or_default();
+        close = true;
+    }
+    let (cursor_row, cursor_column, shift) = (keyboard.row, keyboard.column, keyboard.shift);
+    if close {
+        state.virtual_keyboard = None;
+    }
+    drop(kbgp);
+    if action_handled {
+        egui_ctx.kbgp_clear_input();
+    }
+    if close {
+        return;
+    }
+
+    let mut clicked = None;
+    let area_id = response.id.with("kbgp_virtual_keyboard");
+    // Open below the widget, or above it if the keyboard (as large as it was last frame) does not
+    // fit below.
+    let fits_below = egui_ctx
+        .memory(|memory| memory.area_rect(area_id))
+        .is_none_or(|rect| {
+            response.rect.bottom() + rect.height() <= egui_ctx.screen_rect().bottom()
+        });
+    let (pivot, pos) = if fits_below {
+        (egui::Align2::LEFT_TOP, response.rect.left_bottom())
+    } else {
+        (egui::Align2::LEFT_BOTTOM, response.rect.left_top())
+    };
+    egui::Area::new(area_id)
+        .order(egui::Order::Foreground)
+        .pivot(pivot)
+        .fixed_pos(pos)
+        .constrain(true)
+        .show(egui_ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                for (row_index, row) in layout().enumerate() {
+                    ui.horizontal(|ui| {
+                        for (column_index, key) in row.iter().enumerate() {
+                            let under_cursor = row_index == cursor_row
+                                && column_index == cursor_column.min(row.len() - 1);
+                            let selected = under_cursor || (*key == VirtualKey::Shift && shift);
+                            let button = egui::Button::new(key.label(shift))
+                                .selected(selected)
+                                .min_size(egui::vec2(24.0, 0.0));
+                            if ui.add(button).clicked() {
+                                clicked = Some((row_index, column_index, *key));
+                            }
+                        }
+                    });
+                }
+            });
+        });
+
+    if let Some((row, column, key)) = clicked {
+        let kbgp = kbgp_get(egui_ctx);
+        let mut kbgp = kbgp.lock();
+        if let KbgpState::Navigation(state) = &mut kbgp.state {
+            if let Some(keyboard) = state.virtual_keyboard.as_mut() {
+                keyboard.row = row;
+                keyboard.column = column;
+                keyboard.pending_keys.push(key);
+            }
+        }
+        // Clicking the keyboard makes the `TextEdit` surrender the focus.
+        response.request_focus();
+    }
+}
@@ -0,0 +1,1285 @@
+#![cfg(feature = "testing")]
+
+use std::cell::{Cell, RefCell};
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::{Entity, GamepadAxis, KeyCode};
+use bevy_egui_kbgp::egui;
+use bevy_egui_kbgp::prelude::*;
+use bevy_egui_kbgp::testing::KbgpTestHarness;
+use bevy_egui_kbgp::{
+    kbgp_inspect, kbgp_prevent_loss_of_focus, KbgpDisabledWidgetPolicy, KbgpInputSourceProvider,
+    KbgpPendingInputPrompt, KbgpPrepareNavigation, KbgpPreparePendingInput, KbgpStateInspection,
+    KbgpVirtualKeyboardActions,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+enum UserAction {
+    Cancel,
+}
+
+#[test]
+fn disabled_widgets_are_skipped() {
+    let skip = Cell::new(false);
+    let menu = |ui: &mut egui::Ui| {
+        ui.button("A")
+            .kbgp_navigation()
+            .kbgp_initial_focus()
+            .kbgp_focus_label("a");
+        let disabled = ui
+            .add_enabled(false, egui::Button::new("B"))
+            .kbgp_navigation()
+            .kbgp_focus_label("b");
+        if skip.get() {
+            disabled.kbgp_when_disabled(KbgpDisabledWidgetPolicy::Skip);
+        }
+        ui.button("C").kbgp_navigation().kbgp_focus_label("c");
+    };
+    let mut harness = KbgpTestHarness::new();
+    harness.run_frames(2, menu);
+    harness.assert_focused("a");
+    harness.press(&[KbgpNavCommand::NavigateDown], menu);
+    harness.assert_focused("b");
+
+    skip.set(true);
+    harness.press(&[KbgpNavCommand::NavigateUp], menu);
+    harness.assert_focused("a");
+    harness.press(&[KbgpNavCommand::NavigateDown], menu);
+    harness.assert_focused("c");
+
+    skip.set(false);
+    harness.disabled_widgets = KbgpDisabledWidgetPolicy::Skip;
+    harness.press(&[KbgpNavCommand::NavigateUp], menu);
+    harness.assert_focused("a");
+}
+
+#[test]
+fn focus_leaves_a_widget_that_becomes_skipped() {
+    let disabled = Cell::new(false);
+    let menu = |ui: &mut egui::Ui| {
+        kbgp_prevent_loss_of_focus(ui.ctx());
+        ui.button("A")
+            .kbgp_navigation()
+            .kbgp_initial_focus()
+            .kbgp_focus_label("a");
+        ui.add_enabled(!disabled.get(), egui::Button::new("B"))
+            .kbgp_navigation()
+            .kbgp_when_disabled(KbgpDisabledWidgetPolicy::Skip)
+            .kbgp_focus_label("b");
+        ui.button("C").kbgp_navigation().kbgp_focus_label("c");
+    };
+    let mut harness = KbgpTestHarness::new();
+    harness.run_frames(2, menu);
+    harness.press(&[KbgpNavCommand::NavigateDown], menu);
+    harness.assert_focused("b");
+    disabled.set(true);
+    harness.run_frames(2, menu);
+    harness.assert_focused("a");
+}
+
+#[test]
+fn navigation_follows_the_layout() {
+    let menu = |ui: &mut egui::Ui| {
+        egui::Grid::new("grid").show(ui, |ui| {
+            for row in 0..2u8 {
+                for column in 0..2u8 {
+                    let response = ui
+                        .add_sized([100.0, 20.0], egui::Button::new(format!("{row},{column}")))
+                        .kbgp_navigation()
+                        .kbgp_focus_label((row, column));
+                    if (row, column) == (0, 0) {
+                        response.kbgp_initial_focus();
+                    }
+                }
+                ui.end_row();
+            }
+        });
+    };
+    let mut harness = KbgpTestHarness::new();
+    harness.run_frames(2, menu);
+    harness.assert_focused((0u8, 0u8));
+    harness.press(&[KbgpNavCommand::NavigateRight], menu);
+    harness.assert_focused((0u8, 1u8));
+    harness.press(&[KbgpNavCommand::NavigateDown], menu);
+    harness.assert_focused((1u8, 1u8));
+    harness.press(&[KbgpNavCommand::NavigateLeft], menu);
+    harness.assert_focused((1u8, 0u8));
+    harness.press(&[KbgpNavCommand::NavigateUp], menu);
+    harness.assert_focused((0u8, 0u8));
+}
+
+#[test]
+fn inspection_tells_focus_labels_apart() {
+    let menu = |ui: &mut egui::Ui| {
+        ui.horizontal(|ui| {
+            for i in 0..3u8 {
+                ui.button(format!("Button {i}"))
+                    .kbgp_navigation()
+                    .kbgp_focus_label(i);
+            }
+        });
+    };
+    let mut harness = KbgpTestHarness::new();
+    harness.run_frames(2, menu);
+    let inspection = kbgp_inspect(harness.ctx());
+    let node_of = |label: u8| {
+        inspection
+            .nodes
+            .iter()
+            .find(|node| node.has_focus_label(&label))
+            .unwrap()
+    };
+    assert_eq!(node_of(0).focus_label_types, ["u8"]);
+    assert!(!node_of(0).has_focus_label(&0i32));
+    assert_eq!(node_of(0).right, Some(node_of(1).id));
+    assert_eq!(node_of(1).right, Some(node_of(2).id));
+    assert_eq!(node_of(2).left, Some(node_of(1).id));
+}
+
+#[test]
+fn pending_axis_is_captured_when_released() {
+    let captured = RefCell::new(None);
+    let menu = |ui: &mut egui::Ui| {
+        // Below the minimal threshold, which must not select axes at rest.
+        if let Some(axis) = ui
+            .button("Set axis")
+            .kbgp_navigation()
+            .kbgp_initial_focus()
+            .kbgp_pending_axis(0.0)
+        {
+            *captured.borrow_mut() = Some(axis);
+        }
+    };
+    let stick_x = KbgpAxisInput::GamepadAxis(Entity::PLACEHOLDER, GamepadAxis::LeftStickX);
+    let stick_y = KbgpAxisInput::GamepadAxis(Entity::PLACEHOLDER, GamepadAxis::LeftStickY);
+    let mut harness = KbgpTestHarness::new();
+    harness.run_frames(2, menu);
+
+    // Tilted before the prompt, so it is ignored until it returns to rest.
+    harness.axes = vec![(stick_x.clone(), 0.9), (stick_y.clone(), 0.05)];
+    harness.press(&[KbgpNavCommand::Click], menu);
+    harness.run_frames(2, menu);
+    harness.axes = vec![(stick_x.clone(), 0.05), (stick_y.clone(), 0.05)];
+    harness.run_frames(2, menu);
+    assert_eq!(*captured.borrow(), None);
+
+    harness.axes = vec![(stick_x.clone(), 0.05), (stick_y.clone(), -0.8)];
+    harness.run_frames(2, menu);
+    assert_eq!(*captured.borrow(), None);
+    harness.axes = vec![(stick_x.clone(), 0.05), (stick_y.clone(), 0.05)];
+    harness.run_frame(&[], &[], menu);
+    assert_eq!(*captured.borrow(), Some(stick_y));
+}
+
+#[test]
+fn virtual_cursor_survives_waiting_for_input() {
+    let captured = RefCell::new(None);
+    let menu = |ui: &mut egui::Ui| {
+        if let Some(input) = ui
+            .button("Set key")
+            .kbgp_navigation()
+            .kbgp_initial_focus()
+            .kbgp_pending_input()
+        {
+            *captured.borrow_mut() = Some(input);
+        }
+    };
+    let pointer = |harness: &KbgpTestHarness| {
+        harness
+            .ctx()
+            .input(|input| (input.pointer.hover_pos(), input.pointer.primary_down()))
+    };
+    let mut harness = KbgpTestHarness::new();
+    harness.run_frames(2, menu);
+    harness.press(&[KbgpNavCommand::ToggleVirtualCursor], menu);
+    assert!(pointer(&harness).0.is_some());
+
+    // Clicking the button with the virtual cursor starts waiting for input.
+    harness.press(&[KbgpNavCommand::Click], menu);
+    // The pointer events of a frame reach egui in the next frame.
+    harness.run_frames(3, menu);
+    assert_eq!(
+        kbgp_inspect(harness.ctx()).state,
+        KbgpStateInspection::PendingInput
+    );
+    assert_eq!(pointer(&harness), (None, false));
+
+    harness.press_input(&[KbgpInput::Keyboard(KeyCode::KeyA)], menu);
+    assert_eq!(*captured.borrow(), Some(KbgpInput::Keyboard(KeyCode::KeyA)));
+    // Still toggled on, so the click goes to the pointer again.
+    harness.run_frames(2, menu);
+    for _ in 0..2 {
+        harness.run_frame(&[KbgpNavCommand::Click], &[], menu);
+    }
+    let (pos, down) = pointer(&harness);
+    assert!(pos.is_some());
+    assert!(down);
+}
+
+#[test]
+fn waiting_for_input_is_cancelled_after_the_prompt_timeout() {
+    let captured = RefCell::new(None);
+    let menu = |ui: &mut egui::Ui| {
+        ui.kbgp_set_pending_input_prompt(KbgpPendingInputPrompt {
+            timeout: Some(0.25),
+            ..Default::default()
+        });
+        if let Some(input) = ui
+            .button("Set key")
+            .kbgp_navigation()
+            .kbgp_initial_focus()
+            .kbgp_pending_input()
+        {
+            *captured.borrow_mut() = Some(input);
+        }
+    };
+    let state = |harness: &KbgpTestHarness| kbgp_inspect(harness.ctx()).state;
+    let mut harness = KbgpTestHarness::new();
+    harness.run_frames(2, menu);
+    harness.press(&[KbgpNavCommand::Click], menu);
+    assert_eq!(state(&harness), KbgpStateInspection::PendingInput);
+
+    // 0.25 seconds are 15 frames.
+    harness.run_frames(10, menu);
+    assert_eq!(state(&harness), KbgpStateInspection::PendingInput);
+    harness.run_frames(10, menu);
+    assert!(matches!(
+        state(&harness),
+        KbgpStateInspection::Navigation { .. }
+    ));
+
+    harness.press_input(&[KbgpInput::Keyboard(KeyCode::KeyA)], menu);
+    assert_eq!(*captured.borrow(), None);
+}
+
+/// Input received from another device, held until released.
+#[derive(Default, Clone)]
+struct RemoteControl {
+    held_commands: Arc<Mutex<Vec<KbgpNavCommand>>>,
+    held_inputs: Arc<Mutex<Vec<KbgpInput>>>,
+}
+
+impl KbgpInputSourceProvider for RemoteControl {
+    fn navigation(&mut self, prp: &mut KbgpPrepareNavigation) {
+        for command in self.held_commands.lock().unwrap().iter() {
+            prp.apply_action(command);
+        }
+    }
+
+    fn pending_input(&mut self, prp: &mut KbgpPreparePendingInput) {
+        prp.accept_inputs(self.held_inputs.lock().unwrap().iter().cloned());
+    }
+}
+
+#[test]
+fn input_source_providers_feed_navigation_and_pending_input() {
+    let captured = RefCell::new(None);
+    let menu = |ui: &mut egui::Ui| {
+        ui.button("Play")
+            .kbgp_navigation()
+            .kbgp_initial_focus()
+            .kbgp_focus_label("play");
+        let response = ui
+            .button("Set key")
+            .kbgp_navigation()
+            .kbgp_focus_label("set_key");
+        if let Some(input) = response.kbgp_pending_input() {
+            *captured.borrow_mut() = Some(input);
+        }
+    };
+    let remote = RemoteControl::default();
+    let mut harness = KbgpTestHarness::new();
+    harness.input_source_providers.add(remote.clone());
+    harness.run_frames(2, menu);
+    harness.assert_focused("play");
+
+    let press_remote = |harness: &mut KbgpTestHarness, command: KbgpNavCommand| {
+        *remote.held_commands.lock().unwrap() = vec![command];
+        harness.run_frames(1, menu);
+        remote.held_commands.lock().unwrap().clear();
+        harness.run_frames(1, menu);
+    };
+    press_remote(&mut harness, KbgpNavCommand::NavigateDown);
+    harness.assert_focused("set_key");
+    press_remote(&mut harness, KbgpNavCommand::Click);
+    assert_eq!(
+        kbgp_inspect(harness.ctx()).state,
+        KbgpStateInspection::PendingInput
+    );
+
+    *remote.held_inputs.lock().unwrap() = vec![KbgpInput::Keyboard(KeyCode::KeyB)];
+    harness.run_frames(1, menu);
+    remote.held_inputs.lock().unwrap().clear();
+    harness.run_frames(1, menu);
+    assert_eq!(*captured.borrow(), Some(KbgpInput::Keyboard(KeyCode::KeyB)));
+}
+
+#[test]
+fn held_navigation_repeats() {
+    let menu = |ui: &mut egui::Ui| {
+        for i in 0..10u8 {
+            let response = ui
+                .button(format!("Button {i}"))
+                .kbgp_navigation()
+                .kbgp_focus_label(i);
+            if i == 0 {
+                response.kbgp_initial_focus();
+            }
+        }
+    };
+    let mut harness = KbgpTestHarness::new();
+    // Exactly representable, so that the frames will not drift around the repeat delays.
+    harness.frame_secs = 0.125;
+    harness.run_frames(2, menu);
+
+    // Released before the initial repeat delay (0.6 seconds) passes.
+    for _ in 0..5 {
+        harness.run_frame(&[KbgpNavCommand::NavigateDown], &[], menu);
+    }
+    harness.run_frame(&[], &[], menu);
+    harness.assert_focused(1u8);
+
+    // Repeats after the initial delay, and then in every frame - because the delay between repeats
+    // (0.04 seconds) is shorter than a frame.
+    for _ in 0..8 {
+        harness.run_frame(&[KbgpNavCommand::NavigateDown], &[], menu);
+    }
+    harness.run_frame(&[], &[], menu);
+    harness.assert_focused(5u8);
+}
+
+#[test]
+fn click_release_is_tracked_per_widget() {
+    let clicks = RefCell::new(Vec::new());
+    let releases = RefCell::new(Vec::new());
+    let menu = |ui: &mut egui::Ui| {
+        for label in ["a", "b"] {
+            let mut response = ui.button(label).kbgp_navigation().kbgp_focus_label(label);
+            if label == "a" {
+                response = response.kbgp_initial_focus();
+            }
+            if response.clicked() {
+                clicks.borrow_mut().push(label);
+            }
+            if response.kbgp_click_released() {
+                releases.borrow_mut().push(label);
+            }
+        }
+    };
+    let mut harness = KbgpTestHarness::new();
+    harness.run_frames(2, menu);
+
+    for _ in 0..3 {
+        harness.run_frame(&[KbgpNavCommand::Click], &[], menu);
+    }
+    assert_eq!(*clicks.borrow(), ["a"]);
+    assert!(releases.borrow().is_empty());
+    harness.run_frame(&[], &[], menu);
+    assert_eq!(*releases.borrow(), ["a"]);
+
+    // Moving the focus while the click is held cancels the release.
+    harness.run_frame(&[], &[], menu);
+    harness.run_frame(&[KbgpNavCommand::Click], &[], menu);
+    harness.run_frame(
+        &[KbgpNavCommand::Click, KbgpNavCommand::NavigateDown],
+        &[],
+        menu,
+    );
+    harness.assert_focused("b");
+    harness.run_frames(2, menu);
+    assert_eq!(*clicks.borrow(), ["a", "a"]);
+    assert_eq!(*releases.borrow(), ["a"]);
+}
+
+#[test]
+fn click_pressed_right_after_a_release_is_tracked() {
+    let releases = RefCell::new(Vec::new());
+    let menu = |ui: &mut egui::Ui| {
+        for label in ["a", "b"] {
+            let mut response = ui.button(label).kbgp_navigation().kbgp_focus_label(label);
+            if label == "a" {
+                response = response.kbgp_initial_focus();
+            }
+            if response.kbgp_click_released() {
+                releases.borrow_mut().push(label);
+            }
+        }
+    };
+    let mut harness = KbgpTestHarness::new();
+    harness.run_frames(2, menu);
+    harness.press(&[KbgpNavCommand::Click], menu);
+    assert_eq!(*releases.borrow(), ["a"]);
+
+    // Pressed again in the very next frame, and the focus moves to "b" while it is held.
+    harness.run_frame(&[KbgpNavCommand::Click], &[], menu);
+    harness.run_frame(
+        &[KbgpNavCommand::Click, KbgpNavCommand::NavigateDown],
+        &[],
+        menu,
+    );
+    harness.assert_focused("b");
+    harness.run_frames(2, menu);
+    assert_eq!(*releases.borrow(), ["a"]);
+}
+
+#[test]
+fn auto_navigation() {
+    let menu = |ui: &mut egui::Ui| {
+        ui.button("A").kbgp_focus_label("a");
+        ui.button("Excluded")
+            .kbgp_exclude_navigation()
+            .kbgp_focus_label("excluded");
+        ui.button("B").kbgp_focus_label("b");
+        ui.label("Not interactive");
+        ui.checkbox(&mut true, "C").kbgp_focus_label("c");
+    };
+    let mut harness = KbgpTestHarness::new();
+    harness.auto_navigation = true;
+    harness.run_frames(2, menu);
+    harness.press(&[KbgpNavCommand::NavigateDown], menu);
+    harness.assert_focused("a");
+    harness.press(&[KbgpNavCommand::NavigateDown], menu);
+    harness.assert_focused("b");
+    harness.press(&[KbgpNavCommand::NavigateDown], menu);
+    harness.assert_focused("c");
+    harness.press(&[KbgpNavCommand::NavigateDown], menu);
+    harness.assert_focused("c");
+}
+
+#[test]
+fn auto_navigation_reaches_sliders_but_not_scroll_bars() {
+    let mut value = 5.0;
+    let mut menu = |ui: &mut egui::Ui| {
+        ui.button("A").kbgp_focus_label("a");
+        ui.add(egui::Slider::new(&mut value, 0.0..=10.0).show_value(false))
+            .kbgp_focus_label("slider");
+        egui::ScrollArea::vertical()
+            .max_height(60.0)
+            .show(ui, |ui| {
+                for i in 0..10u8 {
+                    ui.button(format!("Item {i}")).kbgp_focus_label(i);
+                }
+            });
+        ui.button("B").kbgp_focus_label("b");
+    };
+    let mut harness = KbgpTestHarness::new();
+    harness.auto_navigation = true;
+    harness.run_frames(2, &mut menu);
+    let mut visited = Vec::new();
+    for _ in 0..20 {
+        harness.press(&[KbgpNavCommand::NavigateDown], &mut menu);
+        let focused = harness.focused();
+        let label = ["a", "slider", "b"]
+            .into_iter()
+            .find(|label| harness.id_of(label) == focused)
+            .map(str::to_owned)
+            .or_else(|| {
+                (0..10u8)
+                    .find(|i| harness.id_of(i) == focused)
+                    .map(|i| i.to_string())
+            });
+        visited.push(label.expect("focused on a widget that is not part of the menu"));
+    }
+    assert!(visited.iter().any(|label| label == "slider"));
+    assert!(visited.iter().any(|label| label == "9"));
+}
+
+#[test]
+fn adjustable_widgets() {
+    let volume = Cell::new(5.0f64);
+    let menu = |ui: &mut egui::Ui| {
+        ui.button("A")
+            .kbgp_navigation()
+            .kbgp_initial_focus()
+            .kbgp_focus_label("a");
+        let mut value = volume.get();
+        ui.add(egui::Slider::new(&mut value, 0.0..=10.0))
+            .kbgp_navigation()
+            .kbgp_focus_label("volume")
+            .kbgp_adjustable(&mut value, 0.0..=10.0, 1.0);
+        volume.set(value);
+        ui.button("B").kbgp_navigation().kbgp_focus_label("b");
+    };
+    let mut harness = KbgpTestHarness::new();
+    harness.run_frames(2, menu);
+    harness.press(&[KbgpNavCommand::NavigateDown], menu);
+    harness.assert_focused("volume");
+
+    harness.press(&[KbgpNavCommand::NavigateRight], menu);
+    harness.assert_focused("volume");
+    assert_eq!(volume.get(), 6.0);
+    harness.press(&[KbgpNavCommand::NavigateLeft], menu);
+    harness.press(&[KbgpNavCommand::NavigateLeft], menu);
+    assert_eq!(volume.get(), 4.0);
+
+    // The value stays within the range.
+    for _ in 0..10 {
+        harness.press(&[KbgpNavCommand::NavigateRight], menu);
+    }
+    assert_eq!(volume.get(), 10.0);
+
+    // Up and down still navigate.
+    harness.press(&[KbgpNavCommand::NavigateDown], menu);
+    harness.assert_focused("b");
+    assert_eq!(volume.get(), 10.0);
+}
+
+#[test]
+fn tab_bar() {
+    let tab = Cell::new(0);
+    let menu = |ui: &mut egui::Ui| {
+        let mut selected = tab.get();
+        ui.kbgp_tab_bar(&mut selected, [(0, "Video"), (1, "Audio")]);
+        tab.set(selected);
+        if selected == 0 {
+            ui.add_sized([200.0, 20.0], egui::Button::new("Resolution"))
+                .kbgp_navigation()
+                .kbgp_initial_focus()
+                .kbgp_focus_label("resolution");
+            ui.add_sized([200.0, 20.0], egui::Button::new("VSync"))
+                .kbgp_navigation()
+                .kbgp_focus_label("vsync");
+        } else {
+            ui.add_sized([200.0, 20.0], egui::Button::new("Music"))
+                .kbgp_navigation()
+                .kbgp_focus_label("music");
+            ui.add_sized([200.0, 20.0], egui::Button::new("Effects"))
+                .kbgp_navigation()
+                .kbgp_focus_label("effects");
+        }
+    };
+    let mut harness = KbgpTestHarness::new();
+    harness.run_frames(2, menu);
+    harness.assert_focused("resolution");
+    harness.press(&[KbgpNavCommand::NavigateDown], menu);
+    harness.assert_focused("vsync");
+
+    harness.press(&[KbgpNavCommand::NextTab], menu);
+    assert_eq!(tab.get(), 1);
+    harness.press(&[KbgpNavCommand::NavigateDown], menu);
+    harness.assert_focused("music");
+    harness.press(&[KbgpNavCommand::NavigateDown], menu);
+    harness.assert_focused("effects");
+
+    // Each tab remembers its focused widget.
+    harness.press(&[KbgpNavCommand::PrevTab], menu);
+    assert_eq!(tab.get(), 0);
+    harness.assert_focused("vsync");
+    harness.press(&[KbgpNavCommand::PrevTab], menu);
+    assert_eq!(tab.get(), 1);
+    harness.assert_focused("effects");
+}
+
+#[test]
+fn tab_bar_remembers_only_the_focus_inside_its_ui() {
+    let tab = Cell::new(0);
+    let menu = |ui: &mut egui::Ui| {
+        ui.horizontal_top(|ui| {
+            ui.vertical(|ui| {
+                ui.add_sized([100.0, 20.0], egui::Button::new("Side"))
+                    .kbgp_navigation()
+                    .kbgp_focus_label("side");
+            });
+            ui.vertical(|ui| {
+                let mut selected = tab.get();
+                ui.kbgp_tab_bar(&mut selected, [(0, "Video"), (1, "Audio")]);
+                tab.set(selected);
+                let label = if selected == 0 { "resolution" } else { "music" };
+                ui.add_sized([200.0, 20.0], egui::Button::new(label))
+                    .kbgp_navigation()
+                    .kbgp_initial_focus()
+                    .kbgp_focus_label(label);
+            });
+        });
+    };
+    let mut harness = KbgpTestHarness::new();
+    harness.run_frames(2, menu);
+    harness.assert_focused("resolution");
+    harness.press(&[KbgpNavCommand::NavigateLeft], menu);
+    harness.assert_focused("side");
+
+    harness.press(&[KbgpNavCommand::NextTab], menu);
+    assert_eq!(tab.get(), 1);
+    harness.press(&[KbgpNavCommand::PrevTab], menu);
+    assert_eq!(tab.get(), 0);
+    harness.assert_focused("resolution");
+}
+
+#[test]
+fn combo_box() {
+    let selected = Cell::new(0);
+    let menu = |ui: &mut egui::Ui| {
+        ui.button("A")
+            .kbgp_navigation()
+            .kbgp_initial_focus()
+            .kbgp_focus_label("a");
+        let mut value = selected.get();
+        ui.kbgp_combo_box(
+            egui::ComboBox::from_label("Difficulty").selected_text(format!("{value}")),
+            UserAction::Cancel,
+            |ui| {
+                for (index, label) in ["easy", "normal", "hard"].into_iter().enumerate() {
+                    ui.selectable_value(&mut value, index, label)
+                        .kbgp_focus_label(label);
+                }
+            },
+        )
+        .response
+        .kbgp_focus_label("combo");
+        selected.set(value);
+        ui.button("B").kbgp_navigation().kbgp_focus_label("b");
+    };
+    let mut harness = KbgpTestHarness::new();
+    harness.run_frames(2, menu);
+    harness.press(&[KbgpNavCommand::NavigateDown], menu);
+    harness.assert_focused("combo");
+
+    // Opening the popup moves the focus into it, and navigation stays inside it.
+    harness.press(&[KbgpNavCommand::Click], menu);
+    harness.run_frames(2, menu);
+    harness.assert_focused("easy");
+    harness.press(&[KbgpNavCommand::NavigateDown], menu);
+    harness.assert_focused("normal");
+    harness.press(&[KbgpNavCommand::NavigateDown], menu);
+    harness.press(&[KbgpNavCommand::NavigateDown], menu);
+    harness.assert_focused("hard");
+    harness.press(&[KbgpNavCommand::NavigateUp], menu);
+    harness.assert_focused("normal");
+
+    // Selecting an item closes the popup and returns the focus to the combo box.
+    harness.press(&[KbgpNavCommand::Click], menu);
+    harness.run_frames(2, menu);
+    assert_eq!(selected.get(), 1);
+    harness.assert_focused("combo");
+
+    // The cancel action closes the popup without selecting.
+    harness.press(&[KbgpNavCommand::Click], menu);
+    harness.run_frames(2, menu);
+    harness.assert_focused("easy");
+    harness.press(&[KbgpNavCommand::user(UserAction::Cancel)], menu);
+    harness.run_frames(2, menu);
+    harness.assert_focused("combo");
+    assert_eq!(selected.get(), 1);
+    harness.press(&[KbgpNavCommand::NavigateDown], menu);
+    harness.assert_focused("b");
+}
+
+#[test]
+fn back_stack() {
+    let current_menu = Cell::new(0u8);
+    let backs = RefCell::new(Vec::new());
+    let menu = |ui: &mut egui::Ui| {
+        ui.kbgp_push_menu(current_menu.get());
+        if let Some(previous_menu) = ui.kbgp_back::<u8>() {
+            backs.borrow_mut().push(previous_menu);
+            current_menu.set(previous_menu);
+            return;
+        }
+        let depth = current_menu.get();
+        ui.button("Stay")
+            .kbgp_navigation()
+            .kbgp_initial_focus()
+            .kbgp_focus_label(("stay", depth));
+        if ui
+            .button("Deeper")
+            .kbgp_navigation()
+            .kbgp_focus_label(("deeper", depth))
+            .clicked()
+        {
+            ui.kbgp_clear_input();
+            current_menu.set(depth + 1);
+        }
+    };
+    let mut harness = KbgpTestHarness::new();
+    harness.run_frames(2, menu);
+    for depth in 0..2u8 {
+        harness.assert_focused(("stay", depth));
+        harness.press(&[KbgpNavCommand::NavigateDown], menu);
+        harness.press(&[KbgpNavCommand::Click], menu);
+        harness.run_frames(2, menu);
+    }
+    harness.assert_focused(("stay", 2u8));
+
+    // Going back restores the focus of the previous menu.
+    harness.press(&[KbgpNavCommand::Back], menu);
+    harness.run_frames(2, menu);
+    assert_eq!(current_menu.get(), 1);
+    harness.assert_focused(("deeper", 1u8));
+    harness.press(&[KbgpNavCommand::Back], menu);
+    harness.run_frames(2, menu);
+    assert_eq!(current_menu.get(), 0);
+    harness.assert_focused(("deeper", 0u8));
+
+    // The root menu cannot be popped.
+    harness.press(&[KbgpNavCommand::Back], menu);
+    harness.run_frames(2, menu);
+    assert_eq!(current_menu.get(), 0);
+    assert_eq!(*backs.borrow(), [1, 0]);
+}
+
+#[test]
+fn holding_back_goes_back_only_once() {
+    let current_menu = Cell::new(0u8);
+    let menu = |ui: &mut egui::Ui| {
+        ui.kbgp_push_menu(current_menu.get());
+        if let Some(previous_menu) = ui.kbgp_back::<u8>() {
+            current_menu.set(previous_menu);
+            return;
+        }
+        if ui
+            .button("Deeper")
+            .kbgp_navigation()
+            .kbgp_initial_focus()
+            .clicked()
+        {
+            ui.kbgp_clear_input();
+            current_menu.set(current_menu.get() + 1);
+        }
+    };
+    let mut harness = KbgpTestHarness::new();
+    harness.run_frames(2, menu);
+    for _ in 0..2 {
+        harness.press(&[KbgpNavCommand::Click], menu);
+        harness.run_frames(2, menu);
+    }
+    assert_eq!(current_menu.get(), 2);
+
+    // Held for much longer than the repeat delay.
+    for _ in 0..120 {
+        harness.run_frame(&[KbgpNavCommand::Back], &[], menu);
+    }
+    assert_eq!(current_menu.get(), 1);
+}
+
+#[test]
+fn focus_is_restored_when_returning_to_a_menu() {
+    let in_options = Cell::new(false);
+    let menu = |ui: &mut egui::Ui| {
+        ui.kbgp_remember_focus(in_options.get());
+        if !in_options.get() {
+            ui.button("Play")
+                .kbgp_navigation()
+                .kbgp_initial_focus()
+                .kbgp_focus_label("play");
+            if ui
+                .button("Options")
+                .kbgp_navigation()
+                .kbgp_focus_label("options")
+                .clicked()
+            {
+                ui.kbgp_clear_input();
+                in_options.set(true);
+            }
+        } else {
+            ui.button("Audio")
+                .kbgp_navigation()
+                .kbgp_initial_focus()
+                .kbgp_focus_label("audio");
+            ui.button("Video")
+                .kbgp_navigation()
+                .kbgp_focus_label("video");
+            if ui
+                .button("Back")
+                .kbgp_navigation()
+                .kbgp_focus_label("back")
+                .clicked()
+            {
+                ui.kbgp_clear_input();
+                in_options.set(false);
+            }
+        }
+    };
+    let mut harness = KbgpTestHarness::new();
+    harness.run_frames(2, menu);
+    harness.press(&[KbgpNavCommand::NavigateDown], menu);
+    harness.press(&[KbgpNavCommand::Click], menu);
+    harness.run_frames(2, menu);
+    assert!(in_options.get());
+    harness.assert_focused("audio");
+
+    harness.press(&[KbgpNavCommand::NavigateDown], menu);
+    harness.press(&[KbgpNavCommand::NavigateDown], menu);
+    harness.press(&[KbgpNavCommand::Click], menu);
+    harness.run_frames(2, menu);
+    assert!(!in_options.get());
+    harness.assert_focused("options");
+
+    harness.press(&[KbgpNavCommand::Click], menu);
+    harness.run_frames(2, menu);
+    assert!(in_options.get());
+    harness.assert_focused("back");
+}
+
+#[test]
+fn focus_label_can_be_set_for_the_next_frame() {
+    let menu = |ui: &mut egui::Ui| {
+        ui.button("A")
+            .kbgp_navigation()
+            .kbgp_initial_focus()
+            .kbgp_focus_label("a");
+        ui.button("B").kbgp_navigation().kbgp_focus_label("b");
+        ui.button("C").kbgp_navigation().kbgp_focus_label("c");
+    };
+    let mut harness = KbgpTestHarness::new();
+    harness.run_frames(2, menu);
+    harness.assert_focused("a");
+    harness.ctx().kbgp_set_focus_label("c");
+    harness.run_frames(2, menu);
+    harness.assert_focused("c");
+    let id_of_c = harness.id_of(&"c").unwrap();
+    harness.assert_focused(id_of_c);
+}
+
+#[test]
+fn scrolling_without_a_pointer_scrolls_the_focused_scroll_area() {
+    let offsets = Cell::new([0.0f32; 2]);
+    let menu = |ui: &mut egui::Ui| {
+        ui.horizontal(|ui| {
+            for area in 0..2 {
+                let output = egui::ScrollArea::vertical()
+                    .id_salt(area)
+                    .max_height(100.0)
+                    .show(ui, |ui| {
+                        ui.vertical(|ui| {
+                            for i in 0..20 {
+                                ui.button(format!("Button {i}"))
+                                    .kbgp_navigation()
+                                    .kbgp_focus_label((area, i));
+                            }
+                        });
+                    });
+                let mut new_offsets = offsets.get();
+                new_offsets[area] = output.state.offset.y;
+                offsets.set(new_offsets);
+            }
+        });
+    };
+    let mut harness = KbgpTestHarness::new();
+    harness.scroll_velocity = egui::vec2(0.0, -600.0);
+
+    // Nothing is focused - the first `ScrollArea` scrolls.
+    harness.run_frames(5, menu);
+    let [first, second] = offsets.get();
+    assert!(0.0 < first);
+    assert_eq!(second, 0.0);
+
+    harness.scroll_velocity = egui::Vec2::ZERO;
+    harness.ctx().kbgp_set_focus_label((1usize, 0));
+    harness.run_frames(3, menu);
+    harness.assert_focused((1usize, 0));
+    let [first, second] = offsets.get();
+    assert_eq!(second, 0.0);
+
+    harness.scroll_velocity = egui::vec2(0.0, -600.0);
+    harness.run_frames(5, menu);
+    let [first_after, second_after] = offsets.get();
+    assert_eq!(first_after, first);
+    assert!(0.0 < second_after);
+}
+
+#[test]
+fn scrolling_without_a_pointer_stops_at_the_end_of_the_content() {
+    let offset = Cell::new(0.0f32);
+    let overscroll = Cell::new(0.0f32);
+    let menu = |ui: &mut egui::Ui| {
+        let output = egui::ScrollArea::vertical()
+            .max_height(100.0)
+            .show(ui, |ui| {
+                let mut last = None;
+                for i in 0..10 {
+                    last = Some(ui.button(format!("Button {i}")).kbgp_navigation());
+                }
+                last.unwrap().rect.bottom()
+            });
+        offset.set(output.state.offset.y);
+        overscroll.set(output.inner_rect.bottom() - output.inner);
+    };
+    let mut harness = KbgpTestHarness::new();
+    harness.scroll_velocity = egui::vec2(0.0, -600.0);
+    // The `ScrollArea` only marks its viewport once it knows its content does not fit, and it is
+    // found from the frame after that.
+    harness.run_frames(3, menu);
+    assert!((offset.get() - 10.0).abs() < 0.01);
+
+    let mut prev_offset = offset.get();
+    for _ in 0..30 {
+        harness.run_frames(1, menu);
+        let step = offset.get() - prev_offset;
+        prev_offset = offset.get();
+        assert!(step < 10.01);
+        if step < 9.99 {
+            // Clamped to the end of the content.
+            break;
+        }
+    }
+    assert!(50.0 < prev_offset);
+    // Once the end was reached, the content is no longer pushed past it.
+    for _ in 0..5 {
+        harness.run_frames(1, menu);
+        assert_eq!(offset.get(), prev_offset);
+        assert!(
+            overscroll.get() <= 0.01,
+            "drawn {} past the end",
+            overscroll.get()
+        );
+    }
+}
+
+#[test]
+fn clearing_input_does_not_activate_the_next_menu() {
+    let in_second_menu = Cell::new(false);
+    let second_menu_clicks = Cell::new(0);
+    let second_menu_releases = Cell::new(0);
+    let menu = |ui: &mut egui::Ui| {
+        let response = ui
+            .button(if in_second_menu.get() {
+                "Second"
+            } else {
+                "First"
+            })
+            .kbgp_navigation()
+            .kbgp_initial_focus();
+        if in_second_menu.get() {
+            if response.clicked() {
+                second_menu_clicks.set(second_menu_clicks.get() + 1);
+            }
+            if response.kbgp_click_released() {
+                second_menu_releases.set(second_menu_releases.get() + 1);
+            }
+        } else if response.clicked() {
+            ui.kbgp_clear_input();
+            in_second_menu.set(true);
+        }
+    };
+    let mut harness = KbgpTestHarness::new();
+    harness.run_frames(2, menu);
+    // Held for less than the repeat delay.
+    for _ in 0..20 {
+        harness.run_frame(&[KbgpNavCommand::Click], &[], menu);
+    }
+    harness.run_frames(2, menu);
+    assert!(in_second_menu.get());
+    assert_eq!(second_menu_clicks.get(), 0);
+    assert_eq!(second_menu_releases.get(), 0);
+
+    harness.press(&[KbgpNavCommand::Click], menu);
+    assert_eq!(second_menu_clicks.get(), 1);
+}
+
+#[cfg(feature = "recording")]
+#[test]
+fn recording_replay() {
+    use bevy_egui_kbgp::{
+        kbgp_is_replaying, kbgp_start_recording, kbgp_start_replay, kbgp_stop_recording,
+        KbgpRecordedUserActions, KbgpRecorder, KbgpRecording, KbgpReplayer,
+    };
+
+    let cancels = Cell::new(0);
+    let menu = |ui: &mut egui::Ui| {
+        ui.button("Play")
+            .kbgp_navigation()
+            .kbgp_initial_focus()
+            .kbgp_focus_label("play");
+        ui.button("Options")
+            .kbgp_navigation()
+            .kbgp_focus_label("options");
+        ui.button("Quit").kbgp_navigation().kbgp_focus_label("quit");
+        if ui.kbgp_user_action() == Some(UserAction::Cancel) {
+            cancels.set(cancels.get() + 1);
+        }
+    };
+    let user_actions =
+        || KbgpRecordedUserActions::default().with_user_action("cancel", UserAction::Cancel);
+
+    let mut harness = KbgpTestHarness::new();
+    harness.run_frames(2, menu);
+    kbgp_start_recording(harness.ctx(), KbgpRecorder::new(user_actions()));
+    harness.press(&[KbgpNavCommand::NavigateDown], menu);
+    harness.press(&[KbgpNavCommand::NavigateDown], menu);
+    harness.press(&[KbgpNavCommand::user(UserAction::Cancel)], menu);
+    harness.assert_focused("quit");
+    let recording = kbgp_stop_recording(harness.ctx()).unwrap();
+    let recording = KbgpRecording::from_ron(&recording.to_ron().unwrap()).unwrap();
+    assert_eq!(cancels.replace(0), 1);
+
+    let mut harness = KbgpTestHarness::new();
+    harness.run_frames(2, menu);
+    harness.assert_focused("play");
+    kbgp_start_replay(harness.ctx(), KbgpReplayer::new(recording, user_actions()));
+    while kbgp_is_replaying(harness.ctx()) {
+        harness.run_frame(&[], &[], menu);
+    }
+    harness.assert_focused("quit");
+    assert_eq!(cancels.get(), 1);
+}
+
+#[cfg(feature = "recording")]
+#[test]
+fn replay_repeats_held_keys_like_the_recording() {
+    use bevy_egui_kbgp::{
+        kbgp_is_replaying, kbgp_start_recording, kbgp_start_replay, kbgp_stop_recording,
+        KbgpRecordedUserActions, KbgpRecorder, KbgpReplayer,
+    };
+
+    let menu = |ui: &mut egui::Ui| {
+        for index in 0..20 {
+            let button = ui.button(format!("Button {index}")).kbgp_navigation();
+            if index == 0 {
+                button.kbgp_initial_focus().kbgp_focus_label(index);
+            } else {
+                button.kbgp_focus_label(index);
+            }
+        }
+    };
+
+    let mut harness = KbgpTestHarness::new();
+    harness.run_frames(2, menu);
+    kbgp_start_recording(harness.ctx(), KbgpRecorder::default());
+    // Past the repeat delay, so that the held key repeats.
+    for _ in 0..45 {
+        harness.run_frame(&[KbgpNavCommand::NavigateDown], &[], menu);
+    }
+    harness.run_frame(&[], &[], menu);
+    let recording = kbgp_stop_recording(harness.ctx()).unwrap();
+    let focused = harness.focused();
+    assert_ne!(focused, harness.id_of(&1));
+
+    // A different frame rate must not change the number of repeats.
+    let mut harness = KbgpTestHarness::new();
+    harness.frame_secs = 1.0 / 144.0;
+    harness.run_frames(2, menu);
+    kbgp_start_replay(
+        harness.ctx(),
+        KbgpReplayer::new(recording, KbgpRecordedUserActions::default()),
+    );
+    while kbgp_is_replaying(harness.ctx()) {
+        harness.run_frame(&[], &[], menu);
+    }
+    assert_eq!(harness.focused(), focused);
+}
+
+#[cfg(feature = "recording")]
+#[test]
+fn replay_reports_frames_recorded_in_a_different_state() {
+    use bevy_egui_kbgp::{
+        kbgp_is_replaying, kbgp_start_replay, kbgp_take_replay_error, KbgpInput, KbgpRecordedFrame,
+        KbgpRecordedInput, KbgpRecordedUserActions, KbgpRecording, KbgpReplayError, KbgpReplayer,
+    };
+
+    let menu = |ui: &mut egui::Ui| {
+        ui.button("A").kbgp_navigation().kbgp_initial_focus();
+    };
+    let recording = KbgpRecording {
+        frames: vec![
+            KbgpRecordedFrame {
+                time: 0.0,
+                input: KbgpRecordedInput::Navigation(Vec::new()),
+            },
+            KbgpRecordedFrame {
+                time: 1.0 / 60.0,
+                input: KbgpRecordedInput::PendingInput {
+                    inputs: vec![KbgpInput::Keyboard(bevy::prelude::KeyCode::KeyA)],
+                    axes: Vec::new(),
+                },
+            },
+        ],
+    };
+    let mut harness = KbgpTestHarness::new();
+    harness.run_frames(2, menu);
+    kbgp_start_replay(
+        harness.ctx(),
+        KbgpReplayer::new(recording, KbgpRecordedUserActions::default()),
+    );
+    harness.run_frames(2, menu);
+    assert!(!kbgp_is_replaying(harness.ctx()));
+    assert_eq!(
+        kbgp_take_replay_error(harness.ctx()),
+        Some(KbgpReplayError::ExpectedPendingInput { frame: 1 })
+    );
+    assert_eq!(kbgp_take_replay_error(harness.ctx()), None);
+}
+
+#[test]
+fn adjusting_consumes_the_arrow_keys() {
+    use bevy::prelude::{ButtonInput, KeyCode};
+    use bevy_egui_kbgp::{kbgp_prepare, KbgpNavBindings, KbgpPrepare};
+
+    let ctx = egui::Context::default();
+    let bindings = KbgpNavBindings::default();
+    let mut value = 5.0f64;
+    let mut time = 0.0;
+    let mut run_frame = |arrow_right: bool| {
+        let mut keys = ButtonInput::<KeyCode>::default();
+        let mut events = Vec::new();
+        if arrow_right {
+            keys.press(KeyCode::ArrowRight);
+            events.push(egui::Event::Key {
+                key: egui::Key::ArrowRight,
+                physical_key: None,
+                pressed: true,
+                repeat: false,
+                modifiers: Default::default(),
+            });
+        }
+        ctx.begin_pass(egui::RawInput {
+            time: Some(time),
+            events,
+            ..Default::default()
+        });
+        time += 1.0 / 60.0;
+        kbgp_prepare(&ctx, |prp| {
+            if let KbgpPrepare::Navigation(prp) = prp {
+                prp.navigate_keyboard_by_binding(&keys, &bindings.keyboard, true);
+            }
+        });
+        egui::CentralPanel::default().show(&ctx, |ui| {
+            ui.add(egui::Slider::new(&mut value, 0.0..=10.0))
+                .kbgp_navigation()
+                .kbgp_initial_focus()
+                .kbgp_adjustable(&mut value, 0.0..=10.0, 1.0);
+        });
+        let _ = ctx.end_pass();
+    };
+    run_frame(false);
+    run_frame(false);
+    run_frame(true);
+    run_frame(false);
+    assert_eq!(value, 6.0);
+}
+
+#[test]
+fn actions_move_the_caret_while_editing_text() {
+    use bevy::platform::collections::HashMap;
+    use bevy_egui_kbgp::{kbgp_prepare, KbgpPrepare};
+
+    #[derive(PartialEq, Eq, Hash)]
+    enum MenuAction {
+        Right,
+    }
+
+    let ctx = egui::Context::default();
+    let binding: HashMap<MenuAction, KbgpNavCommand> =
+        [(MenuAction::Right, KbgpNavCommand::NavigateRight)]
+            .into_iter()
+            .collect();
+    let mut text = "text".to_owned();
+    let mut time = 0.0;
+    let mut text_edit_id = None;
+    let mut run_frame = |right: bool| {
+        ctx.begin_pass(egui::RawInput {
+            time: Some(time),
+            ..Default::default()
+        });
+        time += 1.0 / 60.0;
+        kbgp_prepare(&ctx, |prp| {
+            if let KbgpPrepare::Navigation(prp) = prp {
+                prp.navigate_by_actions(&binding, |_| right);
+            }
+        });
+        egui::CentralPanel::default().show(&ctx, |ui| {
+            ui.horizontal(|ui| {
+                let response = ui
+                    .text_edit_singleline(&mut text)
+                    .kbgp_navigation()
+                    .kbgp_initial_focus();
+                text_edit_id = Some(response.id);
+                ui.button("Button").kbgp_navigation();
+            });
+        });
+        let _ = ctx.end_pass();
+    };
+    run_frame(false);
+    run_frame(false);
+    run_frame(true);
+    run_frame(false);
+    assert_eq!(ctx.memory(|memory| memory.focused()), text_edit_id);
+}
+
+fn virtual_keyboard_menu<'a>(
+    text: &'a RefCell<String>,
+    space_above: f32,
+) -> impl FnMut(&mut egui::Ui) + 'a {
+    move |ui| {
+        ui.add_space(space_above);
+        let mut text = text.borrow_mut();
+        ui.text_edit_singleline(&mut *text)
+            .kbgp_navigation()
+            .kbgp_initial_focus()
+            .kbgp_focus_label("name")
+            .kbgp_virtual_keyboard(
+                &mut text,
+                &KbgpVirtualKeyboardActions::<UserAction>::default(),
+            );
+        ui.button("Next").kbgp_navigation().kbgp_focus_label("next");
+    }
+}
+
+#[test]
+fn virtual_keyboard_types_until_confirmed() {
+    let text = RefCell::new(String::new());
+    let mut menu = virtual_keyboard_menu(&text, 0.0);
+    let mut harness = KbgpTestHarness::new();
+    harness.run_frames(2, &mut menu);
+    // Opens the keyboard, with the cursor on its first key.
+    harness.press(&[KbgpNavCommand::Click], &mut menu);
+    harness.press(&[KbgpNavCommand::Click], &mut menu);
+    assert_eq!(*text.borrow(), "1");
+
+    // Down to the row of the special keys, and around it to the last one - Done.
+    for _ in 0..4 {
+        harness.press(&[KbgpNavCommand::NavigateDown], &mut menu);
+    }
+    harness.press(&[KbgpNavCommand::NavigateLeft], &mut menu);
+    harness.press(&[KbgpNavCommand::Click], &mut menu);
+    assert_eq!(*text.borrow(), "1");
+    harness.assert_focused("name");
+
+    // Navigation moves between the widgets again.
+    harness.press(&[KbgpNavCommand::NavigateDown], &mut menu);
+    harness.assert_focused("next");
+}
+
+#[test]
+fn virtual_keyboard_cancel_restores_the_text() {
+    let text = RefCell::new("abc".to_owned());
+    let mut menu = virtual_keyboard_menu(&text, 0.0);
+    let mut harness = KbgpTestHarness::new();
+    harness.run_frames(2, &mut menu);
+    harness.press(&[KbgpNavCommand::Click], &mut menu);
+    harness.press(&[KbgpNavCommand::Click], &mut menu);
+    assert!(text.borrow().contains('1'));
+
+    // Cancel is second to last in the row of the special keys.
+    for _ in 0..4 {
+        harness.press(&[KbgpNavCommand::NavigateDown], &mut menu);
+    }
+    for _ in 0..2 {
+        harness.press(&[KbgpNavCommand::NavigateLeft], &mut menu);
+    }
+    harness.press(&[KbgpNavCommand::Click], &mut menu);
+    assert_eq!(*text.borrow(), "abc");
+
+    harness.press(&[KbgpNavCommand::NavigateDown], &mut menu);
+    harness.assert_focused("next");
+}
+
+#[test]
+fn virtual_keyboard_stays_on_screen() {
+    let text = RefCell::new(String::new());
+    let mut harness = KbgpTestHarness::new();
+    // Too close to the bottom of the screen to fit the keyboard below the `TextEdit`.
+    let mut menu = virtual_keyboard_menu(&text, harness.screen_size.y - 80.0);
+    harness.run_frames(2, &mut menu);
+    harness.press(&[KbgpNavCommand::Click], &mut menu);
+    harness.run_frames(2, &mut menu);
+
+    let text_edit_id = harness.id_of(&"name").unwrap();
+    let ctx = harness.ctx();
+    let text_edit_rect =
+        ctx.viewport(|viewport| viewport.prev_pass.widgets.get(text_edit_id).unwrap().rect);
+    let keyboard_rect = ctx
+        .memory(|memory| memory.area_rect(text_edit_id.with("kbgp_virtual_keyboard")))
+        .unwrap();
+    assert!(ctx.screen_rect().contains_rect(keyboard_rect));
+    assert!(keyboard_rect.bottom() <= text_edit_rect.top());
+}
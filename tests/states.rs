@@ -0,0 +1,100 @@
+#![cfg(all(feature = "states", feature = "testing"))]
+
+use std::cell::Cell;
+
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use bevy_egui::{EguiContext, EguiPrimaryContextPass, EguiUserTextures, PrimaryEguiContext};
+use bevy_egui_kbgp::egui;
+use bevy_egui_kbgp::prelude::*;
+use bevy_egui_kbgp::testing::KbgpTestHarness;
+use bevy_egui_kbgp::KbgpStatesPlugin;
+
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+enum MenuState {
+    #[default]
+    Main,
+    Options,
+}
+
+/// An app with [`KbgpStatesPlugin`], and a harness that drives the app's egui context.
+fn create_app() -> (App, KbgpTestHarness) {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin));
+    app.init_state::<MenuState>();
+    app.add_plugins(
+        KbgpStatesPlugin::<MenuState>::default().with_focus_label(MenuState::Options, "back"),
+    );
+    app.init_resource::<EguiUserTextures>();
+    let ctx = app
+        .world_mut()
+        .spawn((EguiContext::default(), PrimaryEguiContext))
+        .get_mut::<EguiContext>()
+        .unwrap()
+        .get_mut()
+        .clone();
+    (app, KbgpTestHarness::with_ctx(ctx))
+}
+
+#[test]
+fn state_transition_clears_input_and_sets_focus_label() {
+    let (mut app, mut harness) = create_app();
+    let options_activations = Cell::new(0);
+    let state = Cell::new(MenuState::Main);
+    let mut menu = |ui: &mut egui::Ui| {
+        // KBGP already prepared the frame, so this is where the plugin's systems run.
+        app.update();
+        app.world_mut().run_schedule(EguiPrimaryContextPass);
+        state.set(*app.world().resource::<State<MenuState>>().get());
+        match state.get() {
+            MenuState::Main => {
+                if ui
+                    .button("Options")
+                    .kbgp_navigation()
+                    .kbgp_initial_focus()
+                    .kbgp_focus_label("options")
+                    .clicked()
+                {
+                    app.world_mut()
+                        .resource_mut::<NextState<MenuState>>()
+                        .set(MenuState::Options);
+                }
+            }
+            MenuState::Options => {
+                let apply = ui
+                    .button("Apply")
+                    .kbgp_navigation()
+                    .kbgp_initial_focus()
+                    .kbgp_focus_label("apply");
+                let back = ui.button("Back").kbgp_navigation().kbgp_focus_label("back");
+                for response in [&apply, &back] {
+                    if response.clicked() || response.kbgp_click_released() {
+                        options_activations.set(options_activations.get() + 1);
+                    }
+                }
+                if back.clicked() {
+                    app.world_mut()
+                        .resource_mut::<NextState<MenuState>>()
+                        .set(MenuState::Main);
+                }
+            }
+        }
+    };
+    harness.run_frames(2, &mut menu);
+    harness.assert_focused("options");
+
+    // Held for less than the repeat delay, and released in the options menu.
+    for _ in 0..20 {
+        harness.run_frame(&[KbgpNavCommand::Click], &[], &mut menu);
+    }
+    harness.run_frames(2, &mut menu);
+    assert_eq!(state.get(), MenuState::Options);
+    assert_eq!(options_activations.get(), 0);
+    // The focus label of the state takes precedence over the initial focus.
+    harness.assert_focused("back");
+
+    harness.press(&[KbgpNavCommand::Click], &mut menu);
+    assert_eq!(options_activations.get(), 1);
+    harness.run_frames(1, &mut menu);
+    assert_eq!(state.get(), MenuState::Main);
+}
@@ -0,0 +1,96 @@
+#![cfg(feature = "bevy_ui")]
+
+use bevy::prelude::*;
+use bevy_egui_kbgp::{KbgpFocused, KbgpNode, KbgpNodeActivated, KbgpUiPlugin};
+
+#[derive(Resource, Default)]
+struct Activations(Vec<(Entity, bool)>);
+
+fn spawn_node(app: &mut App, y: f32) -> Entity {
+    app.world_mut()
+        .spawn((
+            KbgpNode,
+            ComputedNode {
+                size: Vec2::new(100.0, 20.0),
+                ..Default::default()
+            },
+            GlobalTransform::from_translation(Vec3::new(50.0, y, 0.0)),
+            InheritedVisibility::VISIBLE,
+        ))
+        .id()
+}
+
+fn create_app() -> (App, [Entity; 2]) {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins);
+    app.add_plugins(KbgpUiPlugin);
+    app.init_resource::<ButtonInput<KeyCode>>();
+    app.init_resource::<Activations>();
+    app.add_observer(
+        |trigger: Trigger<KbgpNodeActivated>, mut activations: ResMut<Activations>| {
+            activations.0.push((trigger.target(), trigger.released));
+        },
+    );
+    let nodes = [spawn_node(&mut app, 10.0), spawn_node(&mut app, 40.0)];
+    app.world_mut().entity_mut(nodes[0]).insert(KbgpFocused);
+    app.update();
+    (app, nodes)
+}
+
+fn press(app: &mut App, key: KeyCode) {
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(key);
+    app.update();
+}
+
+fn release(app: &mut App, key: KeyCode) {
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .release(key);
+    app.update();
+}
+
+fn focused(app: &mut App) -> Vec<Entity> {
+    app.world_mut()
+        .query_filtered::<Entity, With<KbgpFocused>>()
+        .iter(app.world())
+        .collect()
+}
+
+#[test]
+fn focus_moves_between_nodes() {
+    let (mut app, [first, second]) = create_app();
+    assert_eq!(focused(&mut app), [first]);
+    press(&mut app, KeyCode::ArrowDown);
+    release(&mut app, KeyCode::ArrowDown);
+    assert_eq!(focused(&mut app), [second]);
+    press(&mut app, KeyCode::ArrowUp);
+    release(&mut app, KeyCode::ArrowUp);
+    assert_eq!(focused(&mut app), [first]);
+}
+
+#[test]
+fn activation_is_triggered_on_press_and_on_release() {
+    let (mut app, [first, _]) = create_app();
+    press(&mut app, KeyCode::Enter);
+    app.update();
+    assert_eq!(app.world().resource::<Activations>().0, [(first, false)]);
+    release(&mut app, KeyCode::Enter);
+    assert_eq!(
+        app.world().resource::<Activations>().0,
+        [(first, false), (first, true)]
+    );
+}
+
+#[test]
+fn release_is_not_triggered_if_the_focus_moved() {
+    let (mut app, [first, second]) = create_app();
+    press(&mut app, KeyCode::Enter);
+    press(&mut app, KeyCode::ArrowDown);
+    assert_eq!(focused(&mut app), [second]);
+    release(&mut app, KeyCode::ArrowDown);
+    release(&mut app, KeyCode::Enter);
+    app.update();
+    assert_eq!(app.world().resource::<Activations>().0, [(first, false)]);
+}